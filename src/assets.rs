@@ -0,0 +1,128 @@
+use crate::verify::is_sha1;
+use crate::{AssetIndexInfo, Error, Mcdl, RemoteFile, Result, fs};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+use url::Url;
+
+const RESOURCES_URL: &str = "https://resources.download.minecraft.net/";
+
+/// The asset index referenced by [`VersionManifest::asset_index`](crate::VersionManifest::asset_index)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct AssetIndex {
+    /// Used by 1.6 era indexes, whose objects are also copied by name into
+    /// `assets/virtual/<index id>/`
+    #[serde(rename = "virtual", skip_serializing_if = "is_false", default)]
+    pub is_virtual: bool,
+    /// Used by pre-1.6 indexes, whose objects are also copied by name into
+    /// `<game dir>/resources/`
+    #[serde(skip_serializing_if = "is_false", default)]
+    pub map_to_resources: bool,
+    pub objects: HashMap<String, AssetObject>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct AssetObject {
+    pub hash: String,
+    pub size: u64,
+}
+
+impl AssetIndex {
+    pub async fn fetch(info: &AssetIndexInfo) -> Result<Self> {
+        Mcdl::shared().fetch_asset_index(info).await
+    }
+
+    /// Whether objects also need to be laid out under their names
+    pub fn is_legacy(&self) -> bool {
+        self.is_virtual || self.map_to_resources
+    }
+}
+
+impl AssetObject {
+    /// Location relative to the `objects` directory, `<first two hex digits>/<hash>`.
+    ///
+    /// Fails with [`Error::InvalidSha1`] if the hash is not 40 hex digits.
+    pub fn path(&self) -> Result<String> {
+        if !is_sha1(&self.hash) {
+            return Err(Error::InvalidSha1(self.hash.clone()));
+        }
+        Ok(format!("{}/{}", &self.hash[..2], self.hash))
+    }
+}
+
+impl RemoteFile for AssetObject {
+    fn url(&self) -> Cow<'_, Url> {
+        // Pushed as escaped segments, so even a malformed hash stays on this host
+        let mut url = Url::parse(RESOURCES_URL).expect("the resources URL is valid");
+        url.path_segments_mut()
+            .expect("the resources URL has a path")
+            .pop_if_empty()
+            .push(self.hash.get(..2).unwrap_or_default())
+            .push(&self.hash);
+        Cow::Owned(url)
+    }
+
+    fn sha1(&self) -> &str {
+        &self.hash
+    }
+
+    fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+impl Mcdl {
+    pub async fn fetch_asset_index(&self, info: &AssetIndexInfo) -> Result<AssetIndex> {
+        crate::from_json(&self.download(info).await?)
+    }
+
+    /// Lay out a version's assets under `assets_dir` like the vanilla launcher.
+    ///
+    /// The index is stored as `indexes/<id>.json` and every object as
+    /// `objects/<hh>/<hash>`. Legacy indexes are additionally copied out by name,
+    /// into `virtual/<id>/` or `<game_dir>/resources/`. Files that are already
+    /// present and intact are left alone.
+    ///
+    /// Fails with [`Error::InvalidSha1`] or [`Error::UnsafePath`] before writing
+    /// anything for an object with a malformed hash or a name that would land
+    /// outside its directory.
+    pub async fn install_assets(
+        &self,
+        info: &AssetIndexInfo,
+        assets_dir: &Path,
+        game_dir: &Path,
+    ) -> Result<AssetIndex> {
+        let index_path = fs::enclosed(&assets_dir.join("indexes"), &format!("{}.json", info.id))?;
+        self.download_to(info, &index_path).await?;
+        let index: AssetIndex = crate::from_json(&tokio::fs::read(&index_path).await?)?;
+
+        let objects_dir = assets_dir.join("objects");
+        let virtual_dir = fs::enclosed(&assets_dir.join("virtual"), &info.id)?;
+        let resources_dir = game_dir.join("resources");
+        let mut copies = Vec::new();
+        for (name, object) in &index.objects {
+            let object_path = objects_dir.join(object.path()?);
+            if index.is_virtual {
+                copies.push((
+                    object_path.clone(),
+                    fs::enclosed(&virtual_dir, name)?,
+                    object,
+                ));
+            }
+            if index.map_to_resources {
+                copies.push((object_path, fs::enclosed(&resources_dir, name)?, object));
+            }
+        }
+
+        self.download_all(index.jobs(&objects_dir)?).await?;
+        for (from, to, object) in copies {
+            fs::copy_if_changed(&from, &to, object).await?;
+        }
+        Ok(index)
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
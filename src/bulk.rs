@@ -0,0 +1,199 @@
+use crate::{
+    AssetIndex, Error, Mcdl, Platform, ProgressEvent, RemoteFile, Result, VersionDownloads,
+    VersionManifest, fs,
+};
+use futures_util::StreamExt;
+use futures_util::stream::FuturesUnordered;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::sync::Semaphore;
+use url::Url;
+
+/// A file to fetch in a bulk download, and where to put it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadJob {
+    pub url: Url,
+    pub sha1: String,
+    pub size: u64,
+    pub dest: PathBuf,
+}
+
+impl DownloadJob {
+    pub fn new(file: &impl RemoteFile, dest: impl Into<PathBuf>) -> Self {
+        Self {
+            url: file.url().into_owned(),
+            sha1: file.sha1().to_owned(),
+            size: file.size(),
+            dest: dest.into(),
+        }
+    }
+}
+
+impl RemoteFile for DownloadJob {
+    fn url(&self) -> Cow<'_, Url> {
+        Cow::Borrowed(&self.url)
+    }
+
+    fn sha1(&self) -> &str {
+        &self.sha1
+    }
+
+    fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+impl VersionManifest {
+    /// Library artifacts and natives for `platform`, laid out under
+    /// `libraries_dir` by their maven path.
+    ///
+    /// Fails with [`Error::UnsafePath`] if a path would land outside `libraries_dir`.
+    pub fn library_jobs(
+        &self,
+        platform: &Platform,
+        libraries_dir: &Path,
+    ) -> Result<Vec<DownloadJob>> {
+        self.libraries
+            .iter()
+            .filter(|library| library.is_allowed_on(platform))
+            .flat_map(|library| {
+                library
+                    .artifact()
+                    .into_iter()
+                    .chain(library.native_for(platform))
+            })
+            .map(|file| {
+                let dest = fs::enclosed(libraries_dir, &file.path)?;
+                Ok(DownloadJob::new(file, dest))
+            })
+            .collect()
+    }
+}
+
+impl VersionDownloads {
+    /// Every download, saved in `dir` under its key and the extension of its
+    /// URL, such as `client.jar` or `server_mappings.txt`
+    pub fn jobs(&self, dir: &Path) -> Vec<DownloadJob> {
+        let named = [
+            ("client", Some(&self.client)),
+            ("client_mappings", self.client_mappings.as_ref()),
+            ("server", self.server.as_ref()),
+            ("server_mappings", self.server_mappings.as_ref()),
+        ];
+        named
+            .into_iter()
+            .filter_map(|(name, file)| Some((name, file?)))
+            .chain(self.other.iter().map(|(name, file)| (name.as_str(), file)))
+            .map(|(name, file)| {
+                let extension = Path::new(file.url.path())
+                    .extension()
+                    .map(|extension| format!(".{}", extension.to_string_lossy()))
+                    .unwrap_or_default();
+                DownloadJob::new(file, dir.join(format!("{name}{extension}")))
+            })
+            .collect()
+    }
+}
+
+impl AssetIndex {
+    /// Every object, laid out under `objects_dir` as `<hh>/<hash>`.
+    ///
+    /// Fails with [`Error::InvalidSha1`] if an object's hash is malformed.
+    pub fn jobs(&self, objects_dir: &Path) -> Result<Vec<DownloadJob>> {
+        self.objects
+            .values()
+            .map(|object| Ok(DownloadJob::new(object, objects_dir.join(object.path()?))))
+            .collect()
+    }
+}
+
+impl Mcdl {
+    /// Download many files concurrently with [`download_to`](Self::download_to).
+    ///
+    /// At most [`concurrency`](crate::McdlBuilder::concurrency) jobs run at
+    /// once, and at most [`host_concurrency`](crate::McdlBuilder::host_concurrency)
+    /// against any single host, counting the first mirror a job goes to rather
+    /// than Mojang's host. Jobs sharing a digest are only fetched once.
+    /// Every job is attempted even if some fail, and all failures are returned
+    /// together as [`Error::Bulk`].
+    pub async fn download_all(&self, jobs: impl IntoIterator<Item = DownloadJob>) -> Result<()> {
+        let mut total = 0;
+        let mut bytes = 0;
+        let mut groups: HashMap<String, Vec<DownloadJob>> = HashMap::new();
+        for job in jobs {
+            total += 1;
+            bytes += job.size;
+            let group = groups.entry(job.sha1.to_ascii_lowercase()).or_default();
+            if !group.iter().any(|other| other.dest == job.dest) {
+                group.push(job);
+            }
+        }
+
+        self.progress().emit(ProgressEvent::BatchStarted {
+            files: total,
+            bytes,
+        });
+        let slots = Semaphore::new(self.concurrency());
+        let hosts: HashMap<_, _> = groups
+            .values()
+            .map(|group| {
+                (
+                    self.host(&group[0]),
+                    Semaphore::new(self.host_concurrency()),
+                )
+            })
+            .collect();
+        let mut running: FuturesUnordered<_> = groups
+            .values()
+            .map(|group| async {
+                let _host = hosts[&self.host(&group[0])].acquire().await;
+                let _slot = slots.acquire().await;
+                self.download_group(group).await
+            })
+            .collect();
+
+        let mut failures = Vec::new();
+        while let Some(failed) = running.next().await {
+            failures.extend(failed);
+        }
+        self.progress().emit(ProgressEvent::BatchFinished {
+            files: total,
+            failed: failures.len(),
+        });
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Bulk { total, failures })
+        }
+    }
+
+    /// The host a job is fetched from first, after applying [`Mirrors`](crate::Mirrors)
+    fn host(&self, job: &DownloadJob) -> String {
+        let candidates = self.mirrors().candidates(&job.url).unwrap_or_default();
+        let url = candidates.first().unwrap_or(&job.url);
+        url.host_str().unwrap_or_default().to_owned()
+    }
+
+    /// Fetch the first job of a group of identical files and copy the rest
+    async fn download_group(&self, group: &[DownloadJob]) -> Vec<(DownloadJob, Error)> {
+        let mut failures = Vec::new();
+        let mut done: Option<&Path> = None;
+        for job in group {
+            let result = match (done, self.store()) {
+                (Some(from), None) => {
+                    let copy = fs::copy_if_changed(from, &job.dest, job);
+                    self.progress().observe(&job.url, job.size, copy).await
+                }
+                _ => self.download_to(job, &job.dest).await,
+            };
+            match result {
+                Ok(()) => {
+                    done.get_or_insert(&job.dest);
+                }
+                Err(err) => failures.push((job.clone(), err)),
+            }
+        }
+        failures
+    }
+}
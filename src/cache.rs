@@ -0,0 +1,84 @@
+use crate::transport::Validators;
+use crate::{Result, from_json, fs};
+use bytes::Bytes;
+use sha1::{Digest, Sha1};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// On-disk cache of manifests.
+///
+/// ```text
+/// <dir>/versions/<sha1>.json      version JSONs, immutable for a given sha1
+/// <dir>/roots/<url hash>.json     root manifests
+/// <dir>/roots/<url hash>.meta     their ETag and Last-Modified validators
+/// ```
+#[derive(Debug, Clone)]
+pub struct ManifestCache {
+    dir: PathBuf,
+}
+
+impl ManifestCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// A cached version JSON, if one with this digest is present and intact
+    pub(crate) async fn read_version(&self, sha1: &str) -> Result<Option<Bytes>> {
+        let path = self.version_path(sha1);
+        if !fs::matches(&path, sha1, None).await? {
+            return Ok(None);
+        }
+        Ok(Some(tokio::fs::read(path).await?.into()))
+    }
+
+    pub(crate) async fn write_version(&self, sha1: &str, body: &[u8]) -> Result<()> {
+        fs::write_atomic(&self.version_path(sha1), body).await
+    }
+
+    pub(crate) async fn read_root(&self, url: &Url) -> Result<Option<(Bytes, Validators)>> {
+        let (body_path, meta_path) = self.root_paths(url);
+        let body = match tokio::fs::read(body_path).await {
+            Ok(body) => body,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        // Without validators the body is still usable offline
+        let validators = match tokio::fs::read(meta_path).await {
+            Ok(meta) => from_json(&meta).unwrap_or_default(),
+            Err(_) => Validators::default(),
+        };
+        Ok(Some((body.into(), validators)))
+    }
+
+    pub(crate) async fn write_root(
+        &self,
+        url: &Url,
+        body: &[u8],
+        validators: &Validators,
+    ) -> Result<()> {
+        let (body_path, meta_path) = self.root_paths(url);
+        let meta = serde_json::to_vec(validators).map_err(std::io::Error::from)?;
+        fs::write_atomic(&body_path, body).await?;
+        fs::write_atomic(&meta_path, &meta).await
+    }
+
+    fn version_path(&self, sha1: &str) -> PathBuf {
+        self.dir
+            .join("versions")
+            .join(format!("{}.json", sha1.to_ascii_lowercase()))
+    }
+
+    fn root_paths(&self, url: &Url) -> (PathBuf, PathBuf) {
+        let key = format!("{:x}", Sha1::digest(url.as_str()));
+        let roots = self.dir.join("roots");
+        (
+            roots.join(format!("{key}.json")),
+            roots.join(format!("{key}.meta")),
+        )
+    }
+}
@@ -0,0 +1,850 @@
+use crate::progress::Observer;
+use crate::transport::{ByteStream, Conditional, ReqwestTransport, Transport, Validators};
+use crate::verify::{Verifier, verify, verify_stream};
+use crate::{
+    BlobStore, Error, Library, LibraryDownload, MANIFEST_URL, ManifestCache, Mirrors, Platform,
+    Progress, ProgressEvent, RemoteFile, Result, RetryPolicy, RootManifest, VersionManifest,
+    VersionRelease, from_json, fs,
+};
+use bytes::{Bytes, BytesMut};
+use futures_util::StreamExt;
+use reqwest::Proxy;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use url::Url;
+
+const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+const CONCURRENCY: usize = 16;
+const HOST_CONCURRENCY: usize = 8;
+
+static SHARED: OnceLock<Mcdl> = OnceLock::new();
+
+/// Shared context for talking to Mojang's servers.
+///
+/// Owns the [`Transport`] used for every request, by default a pooled
+/// [`reqwest::Client`], so cloning is cheap and every clone reuses the same
+/// connections. The free-standing `fetch` and `download` methods on the
+/// manifest types go through a default instance of this, or the one given to
+/// [`set_shared`](Self::set_shared).
+///
+/// With a [`ManifestCache`], version JSONs are only fetched once per sha1 and
+/// the root manifest is revalidated with a conditional request, falling back to
+/// the cached copy when the network is unreachable or the server fails with an
+/// error that [`Error::is_transient`] considers temporary.
+///
+/// With a [`BlobStore`], every verified download is kept by sha1 and files are
+/// written to their destination by linking from the store.
+///
+/// Every request is retried according to a [`RetryPolicy`]. To override it for
+/// a single call, use a clone: `mcdl.clone().with_retry(RetryPolicy::none())`.
+/// The same goes for the [`Progress`] observer that downloads report to.
+#[derive(Debug, Clone)]
+pub struct Mcdl {
+    transport: Arc<dyn Transport>,
+    cache: Option<ManifestCache>,
+    store: Option<BlobStore>,
+    retry: RetryPolicy,
+    progress: Observer,
+    mirrors: Mirrors,
+    concurrency: usize,
+    host_concurrency: usize,
+}
+
+#[derive(Debug)]
+pub struct McdlBuilder {
+    builder: reqwest::ClientBuilder,
+    cache: Option<ManifestCache>,
+    store: Option<BlobStore>,
+    retry: RetryPolicy,
+    progress: Observer,
+    mirrors: Mirrors,
+    concurrency: usize,
+    host_concurrency: usize,
+}
+
+impl Mcdl {
+    pub fn new() -> Self {
+        Self::builder()
+            .build()
+            .expect("default HTTP client should always build")
+    }
+
+    pub fn builder() -> McdlBuilder {
+        McdlBuilder {
+            builder: reqwest::Client::builder().user_agent(USER_AGENT),
+            cache: None,
+            store: None,
+            retry: RetryPolicy::default(),
+            progress: Observer::default(),
+            mirrors: Mirrors::default(),
+            concurrency: CONCURRENCY,
+            host_concurrency: HOST_CONCURRENCY,
+        }
+    }
+
+    /// Use an already configured client as is
+    pub fn with_client(client: reqwest::Client) -> Self {
+        Self::with_transport(ReqwestTransport::new(client))
+    }
+
+    /// Fetch everything through a custom transport, such as
+    /// [`MemoryTransport`](crate::MemoryTransport) in tests
+    pub fn with_transport(transport: impl Transport + 'static) -> Self {
+        Self {
+            transport: Arc::new(transport),
+            cache: None,
+            store: None,
+            retry: RetryPolicy::default(),
+            progress: Observer::default(),
+            mirrors: Mirrors::default(),
+            concurrency: CONCURRENCY,
+            host_concurrency: HOST_CONCURRENCY,
+        }
+    }
+
+    pub fn with_cache(mut self, cache: ManifestCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn with_store(mut self, store: BlobStore) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Report every download to `progress`
+    pub fn with_progress(mut self, progress: impl Progress + 'static) -> Self {
+        self.progress = Observer::new(progress);
+        self
+    }
+
+    /// Fetch files from mirrors, see [`Mirrors`]
+    pub fn with_mirrors(mut self, mirrors: Mirrors) -> Self {
+        self.mirrors = mirrors;
+        self
+    }
+
+    /// See [`McdlBuilder::concurrency`]
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// See [`McdlBuilder::host_concurrency`]
+    pub fn with_host_concurrency(mut self, concurrency: usize) -> Self {
+        self.host_concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Make this the instance behind the free-standing convenience methods,
+    /// such as [`DownloadInfo::download`](crate::DownloadInfo::download), so
+    /// they use its cache, store, mirrors and so on.
+    ///
+    /// Only works before the first of those methods ran, and otherwise returns
+    /// `false` and leaves the existing instance in place.
+    #[must_use]
+    pub fn set_shared(self) -> bool {
+        SHARED.set(self).is_ok()
+    }
+
+    /// Instance backing the free-standing convenience methods
+    pub(crate) fn shared() -> &'static Self {
+        SHARED.get_or_init(Self::new)
+    }
+
+    pub fn transport(&self) -> &dyn Transport {
+        &*self.transport
+    }
+
+    pub fn cache(&self) -> Option<&ManifestCache> {
+        self.cache.as_ref()
+    }
+
+    pub fn store(&self) -> Option<&BlobStore> {
+        self.store.as_ref()
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry
+    }
+
+    pub(crate) fn progress(&self) -> &Observer {
+        &self.progress
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    pub fn host_concurrency(&self) -> usize {
+        self.host_concurrency
+    }
+
+    pub fn mirrors(&self) -> &Mirrors {
+        &self.mirrors
+    }
+
+    /// Fetch a whole index that has no sha1 to check it against, retrying
+    /// transient failures. Only goes to a mirror if [`Mirrors::indexes`] allows it.
+    pub(crate) async fn get_index_bytes(&self, url: &Url) -> Result<Bytes> {
+        self.each_candidate(self.mirrors.index_candidates(url)?, |source| async move {
+            self.transport.get_bytes(&source).await
+        })
+        .await
+    }
+
+    /// Run `fetch` against each of the [`Mirrors`] for `url` in turn, retrying
+    /// each one, and return the first success or else the last error
+    async fn mirrored<T, F, Fut>(&self, url: &Url, fetch: F) -> Result<T>
+    where
+        F: FnMut(Url) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.each_candidate(self.mirrors.candidates(url)?, fetch)
+            .await
+    }
+
+    async fn each_candidate<T, F, Fut>(&self, candidates: Vec<Url>, mut fetch: F) -> Result<T>
+    where
+        F: FnMut(Url) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut last = None;
+        for source in candidates {
+            match self.retry.run(|| fetch(source.clone())).await {
+                Ok(value) => return Ok(value),
+                Err(err) => last = Some(err),
+            }
+        }
+        Err(last.expect("there is always at least one candidate"))
+    }
+
+    pub async fn fetch_root_manifest(&self) -> Result<RootManifest> {
+        self.fetch_root_manifest_from_url(MANIFEST_URL).await
+    }
+
+    pub async fn fetch_root_manifest_from_url(&self, url: &str) -> Result<RootManifest> {
+        let url = Url::parse(url)?;
+        let Some(cache) = &self.cache else {
+            return from_json(&self.get_index_bytes(&url).await?);
+        };
+        let cached = cache.read_root(&url).await?;
+        let validators = match &cached {
+            Some((_, validators)) => validators.clone(),
+            None => Validators::default(),
+        };
+        match (
+            self.each_candidate(self.mirrors.index_candidates(&url)?, |source| {
+                let validators = &validators;
+                async move { self.transport.get_conditional(&source, validators).await }
+            })
+            .await,
+            cached,
+        ) {
+            (Ok(Conditional::Modified { body, validators }), _) => {
+                let manifest = from_json(&body)?;
+                cache.write_root(&url, &body, &validators).await?;
+                Ok(manifest)
+            }
+            (Ok(Conditional::NotModified), Some((body, _))) => from_json(&body),
+            // Offline, or the server is having trouble: the cached copy will do
+            (Err(err), Some((body, _))) if err.is_transient() => from_json(&body),
+            (Ok(Conditional::NotModified), None) => from_json(&self.get_index_bytes(&url).await?),
+            (Err(err), _) => Err(err),
+        }
+    }
+
+    pub async fn fetch_version_manifest(
+        &self,
+        release: &VersionRelease,
+    ) -> Result<VersionManifest> {
+        from_json(&self.fetch_version_manifest_bytes(release).await?)
+    }
+
+    /// The version JSON exactly as published, verified against its sha1
+    pub(crate) async fn fetch_version_manifest_bytes(
+        &self,
+        release: &VersionRelease,
+    ) -> Result<Bytes> {
+        if let Some(cache) = &self.cache
+            && let Some(bytes) = cache.read_version(&release.sha1).await?
+        {
+            return Ok(bytes);
+        }
+        let bytes = self
+            .mirrored(&release.url, |source| async move {
+                let bytes = self.transport.get_bytes(&source).await?;
+                verify(&source, &bytes, &release.sha1, None)?;
+                Ok(bytes)
+            })
+            .await?;
+        if let Some(cache) = &self.cache {
+            cache.write_version(&release.sha1, &bytes).await?;
+        }
+        Ok(bytes)
+    }
+
+    pub async fn download(&self, file: &impl RemoteFile) -> Result<Bytes> {
+        let url = file.url();
+        self.progress
+            .observe(&url, file.size(), async {
+                if let Some(store) = &self.store
+                    && let Some(bytes) = store.get(file.sha1()).await?
+                    && verify(&url, &bytes, file.sha1(), Some(file.size())).is_ok()
+                {
+                    return Ok(bytes);
+                }
+                let bytes = self
+                    .mirrored(&url, |source| async move {
+                        self.download_body(file, &source).await
+                    })
+                    .await?;
+                if let Some(store) = &self.store {
+                    store.insert(file.sha1(), &bytes).await?;
+                }
+                Ok(bytes)
+            })
+            .await
+    }
+
+    async fn download_body(&self, file: &impl RemoteFile, source: &Url) -> Result<Bytes> {
+        let url = file.url();
+        let mut stream = self.transport.get_stream(source).await?;
+        let mut verifier = Verifier::new(source, file.sha1(), Some(file.size()));
+        let mut body = BytesMut::new();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            verifier.update(&chunk)?;
+            body.extend_from_slice(&chunk);
+            self.progress.emit(ProgressEvent::Progress {
+                url: &url,
+                downloaded: body.len() as u64,
+                size: file.size(),
+            });
+        }
+        verifier.finish()?;
+        Ok(body.freeze())
+    }
+
+    pub async fn download_as_stream<F: RemoteFile>(&self, file: &F) -> Result<ByteStream> {
+        let url = file.url();
+        self.progress.emit(ProgressEvent::Started {
+            url: &url,
+            size: file.size(),
+        });
+        let fetched = self
+            .mirrored(&url, |source| async move {
+                let stream = self.transport.get_stream(&source).await?;
+                Ok((source, stream))
+            })
+            .await;
+        let (source, stream) = match fetched {
+            Ok(fetched) => fetched,
+            Err(error) => {
+                self.progress.emit(ProgressEvent::Failed {
+                    url: &url,
+                    error: &error,
+                });
+                return Err(error);
+            }
+        };
+        let verifier = Verifier::new(&source, file.sha1(), Some(file.size()));
+        let stream = verify_stream(stream, verifier);
+        Ok(self
+            .progress
+            .observe_stream(stream, url.into_owned(), file.size()))
+    }
+
+    pub async fn download_as_string(&self, file: &impl RemoteFile) -> Result<String> {
+        let bytes = self.download(file).await?;
+        String::from_utf8(bytes.into())
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err).into())
+    }
+
+    /// Download a file to `path`, unless it is already there and intact.
+    ///
+    /// The body is streamed into a `.part` file next to the destination, which
+    /// is only renamed into place once its size and sha1 check out. If the
+    /// transfer is interrupted the `.part` file is kept, and the next call picks
+    /// up where it left off with a range request.
+    pub async fn download_to(&self, file: &impl RemoteFile, path: &Path) -> Result<()> {
+        self.progress
+            .observe(&file.url(), file.size(), async {
+                if fs::is_valid(path, file).await? {
+                    return Ok(());
+                }
+                match &self.store {
+                    Some(store) => {
+                        let blob = store.path(file.sha1())?;
+                        if !fs::is_valid(&blob, file).await? {
+                            self.download_resumable(file, &blob).await?;
+                        }
+                        store.materialize(file.sha1(), path).await
+                    }
+                    None => self.download_resumable(file, path).await,
+                }
+            })
+            .await
+    }
+
+    /// Download to `path` through a `.part` file, resuming it on every retry
+    /// and from every mirror
+    async fn download_resumable(&self, file: &impl RemoteFile, path: &Path) -> Result<()> {
+        self.mirrored(&file.url(), |source| async move {
+            self.download_part(file, &source, path).await
+        })
+        .await
+    }
+
+    async fn download_part(&self, file: &impl RemoteFile, source: &Url, path: &Path) -> Result<()> {
+        let part = fs::with_suffix(path, ".part");
+        if let Some(parent) = part.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let (mut offset, mut verifier) =
+            fs::resume(&part, source, file.sha1(), file.size()).await?;
+        if offset == file.size() {
+            if verifier.clone().finish().is_ok() {
+                tokio::fs::rename(&part, path).await?;
+                return Ok(());
+            }
+            offset = 0;
+            verifier = Verifier::new(source, file.sha1(), Some(file.size()));
+        }
+
+        let (start, stream) = self.transport.get_stream_from(source, offset).await?;
+        if start != offset {
+            verifier = Verifier::new(source, file.sha1(), Some(file.size()));
+        }
+        match self
+            .write_part(file, &part, path, start, stream, verifier)
+            .await
+        {
+            // What was resumed did not belong to this file, say because the
+            // manifest changed the sha1 behind this path, so start over
+            Err(Error::Integrity { .. }) if start > 0 => {
+                let (start, stream) = self.transport.get_stream_from(source, 0).await?;
+                let verifier = Verifier::new(source, file.sha1(), Some(file.size()));
+                self.write_part(file, &part, path, start, stream, verifier)
+                    .await
+            }
+            result => result,
+        }
+    }
+
+    /// Append `stream` to `part` from `start` on, and rename it to `path` once
+    /// complete. The part file is removed if it turns out to be corrupt.
+    async fn write_part(
+        &self,
+        file: &impl RemoteFile,
+        part: &Path,
+        path: &Path,
+        start: u64,
+        mut stream: ByteStream,
+        mut verifier: Verifier,
+    ) -> Result<()> {
+        let url = file.url();
+        let mut out = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(part)
+            .await?;
+        out.set_len(start).await?;
+        out.seek(SeekFrom::Start(start)).await?;
+
+        let mut downloaded = start;
+        if start > 0 {
+            self.progress.emit(ProgressEvent::Progress {
+                url: &url,
+                downloaded,
+                size: file.size(),
+            });
+        }
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            if let Err(err) = verifier.update(&chunk) {
+                drop(out);
+                tokio::fs::remove_file(part).await?;
+                return Err(err);
+            }
+            out.write_all(&chunk).await?;
+            downloaded += chunk.len() as u64;
+            self.progress.emit(ProgressEvent::Progress {
+                url: &url,
+                downloaded,
+                size: file.size(),
+            });
+        }
+        out.flush().await?;
+        drop(out);
+
+        match verifier.finish() {
+            Ok(()) => Ok(tokio::fs::rename(part, path).await?),
+            Err(err) => {
+                tokio::fs::remove_file(part).await?;
+                Err(err)
+            }
+        }
+    }
+
+    /// Download a library and its native classifier for the current OS.
+    ///
+    /// Returns `None` if the library's rules exclude it on this OS. Either half
+    /// may be missing, since old natives-only libraries carry no main artifact.
+    pub async fn download_library(
+        &self,
+        library: &Library,
+    ) -> Result<Option<(Option<(String, Bytes)>, Option<(String, Bytes)>)>> {
+        self.download_library_for(library, &Platform::current())
+            .await
+    }
+
+    /// Like [`download_library`](Self::download_library), for another platform
+    pub async fn download_library_for(
+        &self,
+        library: &Library,
+        platform: &Platform,
+    ) -> Result<Option<(Option<(String, Bytes)>, Option<(String, Bytes)>)>> {
+        if !library.is_allowed_on(platform) {
+            return Ok(None);
+        }
+        let artifact = match library.artifact() {
+            Some(artifact) => Some(self.download_library_file(artifact).await?),
+            None => None,
+        };
+        let native = match library.native_for(platform) {
+            Some(native) => Some(self.download_library_file(native).await?),
+            None => None,
+        };
+        Ok(Some((artifact, native)))
+    }
+
+    pub async fn download_library_as_stream(
+        &self,
+        library: &Library,
+    ) -> Result<Option<(Option<(String, ByteStream)>, Option<(String, ByteStream)>)>> {
+        if !library.is_allowed() {
+            return Ok(None);
+        }
+        let artifact = match library.artifact() {
+            Some(artifact) => Some((
+                artifact.path.clone(),
+                self.download_as_stream(artifact).await?,
+            )),
+            None => None,
+        };
+        let native = match library.native() {
+            Some(native) => Some((native.path.clone(), self.download_as_stream(native).await?)),
+            None => None,
+        };
+        Ok(Some((artifact, native)))
+    }
+
+    async fn download_library_file(&self, file: &LibraryDownload) -> Result<(String, Bytes)> {
+        Ok((file.path.clone(), self.download(file).await?))
+    }
+}
+
+impl Default for Mcdl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl McdlBuilder {
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.builder = self.builder.user_agent(user_agent);
+        self
+    }
+
+    /// Total time allowed for a request, including reading the body
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.builder = self.builder.timeout(timeout);
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.builder = self.builder.connect_timeout(timeout);
+        self
+    }
+
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.builder = self.builder.proxy(proxy);
+        self
+    }
+
+    pub fn pool_idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.builder = self.builder.pool_idle_timeout(timeout);
+        self
+    }
+
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.builder = self.builder.pool_max_idle_per_host(max);
+        self
+    }
+
+    /// Cache manifests in `dir`, see [`ManifestCache`]
+    pub fn cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache = Some(ManifestCache::new(dir));
+        self
+    }
+
+    /// Keep downloads in a content-addressed store, see [`BlobStore`]
+    pub fn store_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.store = Some(BlobStore::new(dir));
+        self
+    }
+
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Report every download to `progress`
+    pub fn progress(mut self, progress: impl Progress + 'static) -> Self {
+        self.progress = Observer::new(progress);
+        self
+    }
+
+    /// Fetch files from mirrors, see [`Mirrors`]
+    pub fn mirrors(mut self, mirrors: Mirrors) -> Self {
+        self.mirrors = mirrors;
+        self
+    }
+
+    /// How many files [`Mcdl::download_all`] fetches at once, 16 by default
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// How many of those may go to the same host, 8 by default
+    pub fn host_concurrency(mut self, concurrency: usize) -> Self {
+        self.host_concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn build(self) -> Result<Mcdl> {
+        let mut mcdl = Mcdl::with_client(self.builder.build()?);
+        mcdl.cache = self.cache;
+        mcdl.store = self.store;
+        mcdl.retry = self.retry;
+        mcdl.progress = self.progress;
+        mcdl.mirrors = self.mirrors;
+        mcdl.concurrency = self.concurrency;
+        mcdl.host_concurrency = self.host_concurrency;
+        Ok(mcdl)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::BoxFuture;
+    use crate::{DownloadJob, MemoryTransport};
+    use reqwest::StatusCode;
+    use serde_json::json;
+    use sha1::{Digest, Sha1};
+    use std::sync::Mutex;
+
+    const URL: &str = "https://piston-data.mojang.com/v1/objects/hello.txt";
+
+    /// Serves `hello` and records the offset of every streamed request
+    #[derive(Debug)]
+    struct Recording {
+        inner: MemoryTransport,
+        offsets: Arc<Mutex<Vec<u64>>>,
+    }
+
+    impl Transport for Recording {
+        fn get_bytes<'a>(&'a self, url: &'a Url) -> BoxFuture<'a, Result<Bytes>> {
+            self.inner.get_bytes(url)
+        }
+
+        fn get_stream<'a>(&'a self, url: &'a Url) -> BoxFuture<'a, Result<ByteStream>> {
+            self.offsets.lock().unwrap().push(0);
+            self.inner.get_stream(url)
+        }
+
+        fn get_stream_from<'a>(
+            &'a self,
+            url: &'a Url,
+            offset: u64,
+        ) -> BoxFuture<'a, Result<(u64, ByteStream)>> {
+            self.offsets.lock().unwrap().push(offset);
+            self.inner.get_stream_from(url, offset)
+        }
+    }
+
+    /// Download `hello` over a `.part` file holding `part`, returning what
+    /// ended up at the destination and the offsets that were requested
+    async fn resume(name: &str, part: &[u8]) -> (Vec<u8>, Vec<u64>) {
+        let dir = std::env::temp_dir().join(format!("mcdl-resume-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let job = DownloadJob {
+            url: Url::parse(URL).unwrap(),
+            sha1: format!("{:x}", Sha1::digest(b"hello")),
+            size: 5,
+            dest: dir.join("hello.txt"),
+        };
+        std::fs::write(fs::with_suffix(&job.dest, ".part"), part).unwrap();
+
+        let offsets = Arc::default();
+        let transport = Recording {
+            inner: MemoryTransport::new().with(job.url.clone(), "hello"),
+            offsets: Arc::clone(&offsets),
+        };
+        let mcdl = Mcdl::with_transport(transport).with_retry(RetryPolicy::none());
+        mcdl.download_to(&job, &job.dest).await.unwrap();
+        assert!(!fs::with_suffix(&job.dest, ".part").exists());
+        let offsets = offsets.lock().unwrap().clone();
+        (std::fs::read(&job.dest).unwrap(), offsets)
+    }
+
+    #[tokio::test]
+    async fn resumes_a_valid_part_file() {
+        assert_eq!(resume("valid", b"hel").await, (b"hello".to_vec(), vec![3]));
+    }
+
+    #[tokio::test]
+    async fn restarts_after_a_corrupt_prefix() {
+        assert_eq!(
+            resume("corrupt", b"jel").await,
+            (b"hello".to_vec(), vec![3, 0])
+        );
+    }
+
+    #[tokio::test]
+    async fn discards_a_part_file_longer_than_the_file() {
+        let resumed = resume("long", b"hello world").await;
+        assert_eq!(resumed, (b"hello".to_vec(), vec![0]));
+    }
+
+    #[tokio::test]
+    async fn completes_a_full_part_file_without_a_request() {
+        assert_eq!(resume("full", b"hello").await, (b"hello".to_vec(), vec![]));
+    }
+
+    #[tokio::test]
+    async fn refetches_a_full_but_corrupt_part_file() {
+        assert_eq!(
+            resume("full-corrupt", b"jello").await,
+            (b"hello".to_vec(), vec![0])
+        );
+    }
+
+    /// Answers conditional requests with `Not Modified`, or with `status` if
+    /// set, and serves plain requests from `inner`
+    #[derive(Debug)]
+    struct Revalidating {
+        inner: MemoryTransport,
+        status: Option<StatusCode>,
+    }
+
+    impl Transport for Revalidating {
+        fn get_bytes<'a>(&'a self, url: &'a Url) -> BoxFuture<'a, Result<Bytes>> {
+            self.inner.get_bytes(url)
+        }
+
+        fn get_stream<'a>(&'a self, url: &'a Url) -> BoxFuture<'a, Result<ByteStream>> {
+            self.inner.get_stream(url)
+        }
+
+        fn get_conditional<'a>(
+            &'a self,
+            url: &'a Url,
+            _: &'a Validators,
+        ) -> BoxFuture<'a, Result<Conditional>> {
+            Box::pin(async move {
+                match self.status {
+                    Some(status) => Err(Error::Status {
+                        url: url.clone(),
+                        status,
+                    }),
+                    None => Ok(Conditional::NotModified),
+                }
+            })
+        }
+    }
+
+    fn root(latest: &str) -> String {
+        json!({
+            "latest": { "release": latest, "snapshot": latest },
+            "versions": [],
+        })
+        .to_string()
+    }
+
+    /// Fetch the root manifest with `cached` in the cache, while the server
+    /// would serve release `2.0` and answers revalidation with `status`
+    async fn revalidate(
+        name: &str,
+        cached: Option<&str>,
+        status: Option<StatusCode>,
+    ) -> Result<String> {
+        let dir = std::env::temp_dir().join(format!("mcdl-root-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let url = Url::parse(MANIFEST_URL).unwrap();
+        let cache = ManifestCache::new(dir);
+        if let Some(cached) = cached {
+            let validators = Validators {
+                etag: Some("\"cached\"".to_owned()),
+                last_modified: None,
+            };
+            cache
+                .write_root(&url, root(cached).as_bytes(), &validators)
+                .await
+                .unwrap();
+        }
+        let transport = Revalidating {
+            inner: MemoryTransport::new().with(url, root("2.0")),
+            status,
+        };
+        let mcdl = Mcdl::with_transport(transport)
+            .with_cache(cache)
+            .with_retry(RetryPolicy::none());
+        Ok(mcdl.fetch_root_manifest().await?.latest.release)
+    }
+
+    #[tokio::test]
+    async fn uses_the_cached_root_when_not_modified() {
+        let release = revalidate("not-modified", Some("1.0"), None).await;
+        assert_eq!(release.unwrap(), "1.0");
+    }
+
+    #[tokio::test]
+    async fn refetches_the_root_when_not_modified_but_not_cached() {
+        let release = revalidate("not-cached", None, None).await;
+        assert_eq!(release.unwrap(), "2.0");
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_the_cached_root_on_transient_errors() {
+        let unavailable = Some(StatusCode::SERVICE_UNAVAILABLE);
+        let release = revalidate("unavailable", Some("1.0"), unavailable).await;
+        assert_eq!(release.unwrap(), "1.0");
+    }
+
+    #[tokio::test]
+    async fn reports_permanent_errors_despite_a_cached_root() {
+        let forbidden = Some(StatusCode::FORBIDDEN);
+        let err = revalidate("forbidden", Some("1.0"), forbidden)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, Error::Status { status, .. } if status == StatusCode::FORBIDDEN),
+            "{err}"
+        );
+    }
+}
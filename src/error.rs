@@ -0,0 +1,76 @@
+use crate::DownloadJob;
+use reqwest::StatusCode;
+use std::fmt;
+use url::Url;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The server answered with a non-success status code.
+    #[error("{url} responded with {status}")]
+    Status { url: Url, status: StatusCode },
+    /// The request could not be sent or the response body could not be read.
+    #[error("transport error: {0}")]
+    Transport(#[from] reqwest::Error),
+    /// A response body was not the JSON document we expected.
+    #[error("malformed JSON at `{path}`: {source}")]
+    Json {
+        path: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("invalid URL: {0}")]
+    InvalidUrl(#[from] url::ParseError),
+    /// Downloaded bytes did not match the digest or size from the manifest.
+    #[error("integrity check failed for {url}: {mismatch}")]
+    Integrity { url: Url, mismatch: Box<Mismatch> },
+    /// No version with this id is listed in the root manifest.
+    #[error("unknown version `{0}`")]
+    UnknownVersion(String),
+    /// Mojang ships no Java runtime with this name for the platform.
+    #[error("no Java runtime `{component}` for {platform}")]
+    UnknownRuntime { component: String, platform: String },
+    /// A digest taken from a manifest is not 40 hex digits.
+    #[error("`{0}` is not a sha1 digest")]
+    InvalidSha1(String),
+    /// A path taken from a manifest is absolute or climbs out of its directory.
+    #[error("refusing path `{0}` that escapes its directory")]
+    UnsafePath(String),
+    /// Some jobs of a bulk download failed, each with its own error.
+    #[error("{} of {total} downloads failed", failures.len())]
+    Bulk {
+        total: usize,
+        failures: Vec<(DownloadJob, Error)>,
+    },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    Sha1 { expected: String, actual: String },
+    Size { expected: u64, actual: u64 },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sha1 { expected, actual } => {
+                write!(f, "expected sha1 {expected}, got {actual}")
+            }
+            Self::Size { expected, actual } => {
+                write!(f, "expected {expected} bytes, got {actual}")
+            }
+        }
+    }
+}
+
+impl From<serde_path_to_error::Error<serde_json::Error>> for Error {
+    fn from(err: serde_path_to_error::Error<serde_json::Error>) -> Self {
+        Self::Json {
+            path: err.path().to_string(),
+            source: err.into_inner(),
+        }
+    }
+}
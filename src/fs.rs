@@ -0,0 +1,182 @@
+use crate::verify::Verifier;
+use crate::{Error, RemoteFile, Result};
+use sha1::{Digest, Sha1};
+use std::ffi::OsString;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use tokio::io::AsyncReadExt;
+use url::Url;
+
+/// Whether `path` already holds exactly the bytes `file` describes
+pub(crate) async fn is_valid(path: &Path, file: &impl RemoteFile) -> Result<bool> {
+    matches(path, file.sha1(), Some(file.size())).await
+}
+
+/// Whether `path` exists with the given digest and, if known, size
+pub(crate) async fn matches(path: &Path, sha1: &str, size: Option<u64>) -> Result<bool> {
+    match tokio::fs::metadata(path).await {
+        Ok(metadata) if size.is_some_and(|size| metadata.len() != size) => return Ok(false),
+        Ok(_) => {}
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err.into()),
+    }
+    let bytes = tokio::fs::read(path).await?;
+    let mut hasher = Sha1::new();
+    hasher.update(&bytes);
+    Ok(format!("{:x}", hasher.finalize()).eq_ignore_ascii_case(sha1))
+}
+
+/// Hash what an interrupted download already left in `part`.
+///
+/// Returns how many bytes can be kept and a verifier primed with them. A part
+/// file longer than the expected size is useless and starts over from zero.
+pub(crate) async fn resume(
+    part: &Path,
+    url: &Url,
+    sha1: &str,
+    size: u64,
+) -> Result<(u64, Verifier)> {
+    let mut verifier = Verifier::new(url, sha1, Some(size));
+    let mut file = match tokio::fs::File::open(part).await {
+        Ok(file) => file,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok((0, verifier)),
+        Err(err) => return Err(err.into()),
+    };
+    if file.metadata().await?.len() > size {
+        return Ok((0, verifier));
+    }
+    let mut offset = 0;
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buf).await?;
+        if read == 0 {
+            return Ok((offset, verifier));
+        }
+        verifier.update(&buf[..read])?;
+        offset += read as u64;
+    }
+}
+
+/// Join `relative`, a path taken from a manifest, onto `base`, refusing
+/// anything absolute or with a `..` that could climb out of `base`
+pub(crate) fn enclosed(base: &Path, relative: &str) -> Result<PathBuf> {
+    let path = Path::new(relative);
+    let normal = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if relative.is_empty() || !normal {
+        return Err(Error::UnsafePath(relative.to_owned()));
+    }
+    Ok(base.join(path))
+}
+
+/// Follow a symlink at `link`, relative to some root, to `target`, relative
+/// to the link's directory, without touching the disk. Returns every path
+/// the walk passes through, relative to the root and ending with where the
+/// link points, or `None` if it climbs out of the root or is absolute.
+pub(crate) fn link_steps(link: &str, target: &str) -> Option<Vec<PathBuf>> {
+    let mut current = Path::new(link).parent().unwrap_or(Path::new("")).to_owned();
+    let mut steps = Vec::new();
+    for component in Path::new(target).components() {
+        match component {
+            Component::Normal(name) => current.push(name),
+            Component::CurDir => continue,
+            Component::ParentDir if current.pop() => {}
+            _ => return None,
+        }
+        steps.push(current.clone());
+    }
+    Some(steps)
+}
+
+/// Whether some directory between `base` and `base/relative` is already a
+/// symlink on disk, which a write to `relative` would follow
+pub(crate) async fn has_symlinked_parent(base: &Path, relative: &str) -> Result<bool> {
+    let parents: Vec<_> = Path::new(relative).ancestors().skip(1).collect();
+    for parent in parents.into_iter().rev() {
+        if parent.as_os_str().is_empty() {
+            continue;
+        }
+        match tokio::fs::symlink_metadata(base.join(parent)).await {
+            Ok(metadata) if metadata.file_type().is_symlink() => return Ok(true),
+            Ok(_) => {}
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(false),
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(false)
+}
+
+/// Write through a sibling temporary file so readers never see a partial file
+pub(crate) async fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let temporary = with_suffix(path, ".tmp");
+    tokio::fs::write(&temporary, contents).await?;
+    tokio::fs::rename(&temporary, path).await?;
+    Ok(())
+}
+
+/// Copy `from` to `to` unless `to` already holds the same bytes
+pub(crate) async fn copy_if_changed(from: &Path, to: &Path, file: &impl RemoteFile) -> Result<()> {
+    if is_valid(to, file).await? {
+        return Ok(());
+    }
+    if let Some(parent) = to.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::copy(from, to).await?;
+    Ok(())
+}
+
+pub(crate) fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    name.into()
+}
+
+#[cfg(unix)]
+pub(crate) async fn set_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut permissions = tokio::fs::metadata(path).await?.permissions();
+    permissions.set_mode(permissions.mode() | 0o111);
+    tokio::fs::set_permissions(path, permissions).await?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub(crate) async fn set_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// Point `link` at `target`, replacing whatever file, directory or link was
+/// there before
+#[cfg(unix)]
+pub(crate) async fn symlink(target: &str, link: &Path) -> Result<()> {
+    if let Some(parent) = link.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    match tokio::fs::read_link(link).await {
+        Ok(existing) if existing == Path::new(target) => return Ok(()),
+        Ok(_) => tokio::fs::remove_file(link).await?,
+        Err(err) if err.kind() == ErrorKind::NotFound => {}
+        // Not a link at all
+        Err(err) if err.kind() == ErrorKind::InvalidInput => {
+            if tokio::fs::symlink_metadata(link).await?.is_dir() {
+                tokio::fs::remove_dir_all(link).await?;
+            } else {
+                tokio::fs::remove_file(link).await?;
+            }
+        }
+        Err(err) => return Err(err.into()),
+    }
+    tokio::fs::symlink(target, link).await?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub(crate) async fn symlink(_target: &str, _link: &Path) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,192 @@
+use crate::{
+    AssetIndex, DownloadJob, Library, Mcdl, Platform, Result, VersionManifest, VersionRelease, fs,
+    natives,
+};
+use std::collections::HashSet;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Installs versions into a game directory the way the official launcher does.
+///
+/// ```text
+/// <game dir>/
+///   versions/<id>/<id>.json
+///   versions/<id>/<id>.jar
+///   versions/<id>/natives/
+///   libraries/<maven path>
+///   assets/
+/// ```
+///
+/// Files that already exist and match their manifest digest are not downloaded
+/// again, so re-running an install only repairs what is missing or corrupt.
+#[derive(Debug, Clone)]
+pub struct Installer {
+    mcdl: Mcdl,
+    game_dir: PathBuf,
+    platform: Platform,
+}
+
+impl Installer {
+    pub fn new(game_dir: impl Into<PathBuf>) -> Self {
+        Self::with_mcdl(Mcdl::shared().clone(), game_dir)
+    }
+
+    pub fn with_mcdl(mcdl: Mcdl, game_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            mcdl,
+            game_dir: game_dir.into(),
+            platform: Platform::current(),
+        }
+    }
+
+    /// Install libraries and natives for another machine than this one
+    pub fn platform(mut self, platform: Platform) -> Self {
+        self.platform = platform;
+        self
+    }
+
+    pub fn game_dir(&self) -> &Path {
+        &self.game_dir
+    }
+
+    pub fn version_dir(&self, id: &str) -> PathBuf {
+        self.game_dir.join("versions").join(id)
+    }
+
+    pub fn natives_dir(&self, id: &str) -> PathBuf {
+        self.version_dir(id).join("natives")
+    }
+
+    pub fn libraries_dir(&self) -> PathBuf {
+        self.game_dir.join("libraries")
+    }
+
+    pub fn assets_dir(&self) -> PathBuf {
+        self.game_dir.join("assets")
+    }
+
+    /// Look a version up in the root manifest and install it. Besides ids,
+    /// `latest` and `latest-snapshot` are understood.
+    pub async fn install(&self, id: &str) -> Result<VersionManifest> {
+        let root = self.mcdl.fetch_root_manifest().await?;
+        self.install_release(root.resolve(id)?).await
+    }
+
+    /// Fails with [`Error::UnsafePath`](crate::Error::UnsafePath) if the id or
+    /// a library path from the manifest would land outside the game directory
+    pub async fn install_release(&self, release: &VersionRelease) -> Result<VersionManifest> {
+        let version_dir = fs::enclosed(&self.game_dir.join("versions"), &release.id)?;
+        let json_path = version_dir.join(format!("{}.json", release.id));
+        if !fs::matches(&json_path, &release.sha1, None).await? {
+            let bytes = self.mcdl.fetch_version_manifest_bytes(release).await?;
+            fs::write_atomic(&json_path, &bytes).await?;
+        }
+        let manifest: VersionManifest = crate::from_json(&tokio::fs::read(&json_path).await?)?;
+
+        let jar_path = version_dir.join(format!("{}.jar", release.id));
+        let mut jobs: Vec<_> = manifest
+            .downloads
+            .iter()
+            .map(|downloads| DownloadJob::new(&downloads.client, &jar_path))
+            .collect();
+        jobs.extend(manifest.library_jobs(&self.platform, &self.libraries_dir())?);
+        if let Some(config) = manifest
+            .logging
+            .as_ref()
+            .and_then(|logging| logging.client.as_ref())
+        {
+            let path = fs::enclosed(&self.assets_dir().join("log_configs"), &config.file.id)?;
+            jobs.push(DownloadJob::new(&config.file, path));
+        }
+        self.mcdl.download_all(jobs).await?;
+
+        for library in &manifest.libraries {
+            self.extract_natives(library, &release.id).await?;
+        }
+
+        if let Some(asset_index) = &manifest.asset_index {
+            self.mcdl
+                .install_assets(asset_index, &self.assets_dir(), &self.game_dir)
+                .await?;
+        }
+
+        Ok(manifest)
+    }
+
+    /// Digests of every file the versions installed here depend on, for
+    /// [`BlobStore::gc`](crate::BlobStore::gc)
+    pub async fn referenced_blobs(&self) -> Result<HashSet<String>> {
+        let mut referenced = HashSet::new();
+        let mut versions = match tokio::fs::read_dir(self.game_dir.join("versions")).await {
+            Ok(versions) => versions,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(referenced),
+            Err(err) => return Err(err.into()),
+        };
+        while let Some(entry) = versions.next_entry().await? {
+            let id = entry.file_name().to_string_lossy().into_owned();
+            let json_path = entry.path().join(format!("{id}.json"));
+            let manifest: VersionManifest = match tokio::fs::read(&json_path).await {
+                Ok(bytes) => crate::from_json(&bytes)?,
+                Err(err) if err.kind() == ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            };
+            self.collect_references(&manifest, &mut referenced).await?;
+        }
+        Ok(referenced)
+    }
+
+    async fn collect_references(
+        &self,
+        manifest: &VersionManifest,
+        referenced: &mut HashSet<String>,
+    ) -> Result<()> {
+        let mut digests: Vec<_> = manifest
+            .downloads
+            .iter()
+            .map(|downloads| &downloads.client.sha1)
+            .collect();
+        for library in &manifest.libraries {
+            let downloads = &library.downloads;
+            digests.extend(downloads.artifact.iter().map(|file| &file.sha1));
+            digests.extend(downloads.classifiers.values().map(|file| &file.sha1));
+        }
+        if let Some(config) = manifest
+            .logging
+            .as_ref()
+            .and_then(|logging| logging.client.as_ref())
+        {
+            digests.push(&config.file.sha1);
+        }
+        referenced.extend(digests.into_iter().map(|sha1| sha1.to_ascii_lowercase()));
+
+        if let Some(info) = &manifest.asset_index {
+            referenced.insert(info.sha1.to_ascii_lowercase());
+            let index_path = self
+                .assets_dir()
+                .join("indexes")
+                .join(format!("{}.json", info.id));
+            if let Ok(bytes) = tokio::fs::read(index_path).await {
+                let index: AssetIndex = crate::from_json(&bytes)?;
+                referenced.extend(
+                    index
+                        .objects
+                        .values()
+                        .map(|object| object.hash.to_ascii_lowercase()),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    async fn extract_natives(&self, library: &Library, id: &str) -> Result<()> {
+        if !library.is_allowed_on(&self.platform) {
+            return Ok(());
+        }
+        if let Some(native) = library.native_for(&self.platform) {
+            let path = fs::enclosed(&self.libraries_dir(), &native.path)?;
+            let jar = tokio::fs::read(&path).await?;
+            natives::extract(jar.into(), &self.natives_dir(id), &library.extract).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,564 @@
+use crate::{Argument, OsName, Platform, Profile, RuleContext, VersionManifest, feature};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// JVM arguments the vanilla launcher uses for versions from before
+/// `arguments` existed, the same as 1.13 ships in its manifest
+static LEGACY_JVM_ARGUMENTS: LazyLock<Vec<Argument>> = LazyLock::new(|| {
+    serde_json::from_str(
+        r#"[
+            { "rules": [{ "action": "allow", "os": { "name": "osx" } }],
+              "value": ["-XstartOnFirstThread"] },
+            { "rules": [{ "action": "allow", "os": { "name": "windows" } }],
+              "value": "-XX:HeapDumpPath=MojangTricksIntelDriversForPerformance_javaw.exe_minecraft.exe.heapdump" },
+            { "rules": [{ "action": "allow", "os": { "name": "windows", "version": "^10\\." } }],
+              "value": ["-Dos.name=Windows 10", "-Dos.version=10.0"] },
+            { "rules": [{ "action": "allow", "os": { "arch": "x86" } }],
+              "value": "-Xss1M" },
+            "-Djava.library.path=${natives_directory}",
+            "-Dminecraft.launcher.brand=${launcher_name}",
+            "-Dminecraft.launcher.version=${launcher_version}",
+            "-cp",
+            "${classpath}"
+        ]"#,
+    )
+    .expect("legacy JVM arguments are valid")
+});
+
+/// Builds the `java` command line for an installed version.
+///
+/// Paths default to the layout produced by [`Installer`](crate::Installer)
+/// under the game directory. Nothing is spawned, so the result can be compared
+/// against known launcher output or handed to [`std::process::Command`].
+#[derive(Debug, Clone)]
+pub struct LaunchSpec<'a> {
+    manifest: &'a VersionManifest,
+    java: PathBuf,
+    game_dir: PathBuf,
+    natives_dir: PathBuf,
+    libraries_dir: PathBuf,
+    assets_dir: PathBuf,
+    client_jar: PathBuf,
+    profile: Option<Profile>,
+    resolution: Option<(u32, u32)>,
+    context: RuleContext,
+    launcher_name: String,
+    launcher_version: String,
+    placeholders: HashMap<String, String>,
+}
+
+impl<'a> LaunchSpec<'a> {
+    pub fn new(manifest: &'a VersionManifest, game_dir: impl Into<PathBuf>) -> Self {
+        let game_dir = game_dir.into();
+        let version_dir = game_dir.join("versions").join(&manifest.id);
+        Self {
+            manifest,
+            java: PathBuf::from("java"),
+            natives_dir: version_dir.join("natives"),
+            libraries_dir: game_dir.join("libraries"),
+            assets_dir: game_dir.join("assets"),
+            client_jar: version_dir.join(format!("{}.jar", manifest.id)),
+            game_dir,
+            profile: None,
+            resolution: None,
+            context: RuleContext::default(),
+            launcher_name: env!("CARGO_PKG_NAME").to_owned(),
+            launcher_version: env!("CARGO_PKG_VERSION").to_owned(),
+            placeholders: HashMap::new(),
+        }
+    }
+
+    pub fn java(mut self, java: impl Into<PathBuf>) -> Self {
+        self.java = java.into();
+        self
+    }
+
+    pub fn natives_dir(mut self, natives_dir: impl Into<PathBuf>) -> Self {
+        self.natives_dir = natives_dir.into();
+        self
+    }
+
+    pub fn libraries_dir(mut self, libraries_dir: impl Into<PathBuf>) -> Self {
+        self.libraries_dir = libraries_dir.into();
+        self
+    }
+
+    pub fn assets_dir(mut self, assets_dir: impl Into<PathBuf>) -> Self {
+        self.assets_dir = assets_dir.into();
+        self
+    }
+
+    pub fn client_jar(mut self, client_jar: impl Into<PathBuf>) -> Self {
+        self.client_jar = client_jar.into();
+        self
+    }
+
+    pub fn profile(mut self, profile: Profile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// Window size, which also turns on the `has_custom_resolution` feature
+    pub fn resolution(mut self, width: u32, height: u32) -> Self {
+        self.resolution = Some((width, height));
+        self
+    }
+
+    /// Platform used for library and argument rules, the host by default
+    pub fn platform(mut self, platform: Platform) -> Self {
+        self.context.platform = platform;
+        self
+    }
+
+    pub fn feature(mut self, name: impl Into<String>, enabled: bool) -> Self {
+        self.context.features.insert(name.into(), enabled);
+        self
+    }
+
+    pub fn launcher(mut self, name: impl Into<String>, version: impl Into<String>) -> Self {
+        self.launcher_name = name.into();
+        self.launcher_version = version.into();
+        self
+    }
+
+    /// Set or override the value substituted for `${key}`
+    pub fn placeholder(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.placeholders.insert(key.into(), value.into());
+        self
+    }
+
+    /// Library artifacts allowed on the target platform, then the client jar
+    pub fn classpath(&self) -> Vec<PathBuf> {
+        let mut classpath: Vec<PathBuf> = Vec::new();
+        for library in &self.manifest.libraries {
+            if !self.context.allows(&library.rules) {
+                continue;
+            }
+            if let Some(artifact) = library.artifact() {
+                let path = self.libraries_dir.join(&artifact.path);
+                if !classpath.contains(&path) {
+                    classpath.push(path);
+                }
+            }
+        }
+        classpath.push(self.client_jar.clone());
+        classpath
+    }
+
+    /// The values substituted for `${...}` placeholders
+    pub fn placeholders(&self) -> HashMap<String, String> {
+        let manifest = self.manifest;
+        let separator = match self.context.platform.os.family() {
+            OsName::Windows => ";",
+            _ => ":",
+        };
+        let classpath = self
+            .classpath()
+            .iter()
+            .map(|path| path_str(path))
+            .collect::<Vec<_>>()
+            .join(separator);
+        let assets_index_name = manifest
+            .asset_index
+            .as_ref()
+            .map(|index| index.id.clone())
+            .or_else(|| manifest.assets.clone())
+            .unwrap_or_default();
+
+        let mut values: HashMap<String, String> = [
+            ("version_name", manifest.id.clone()),
+            ("version_type", manifest.kind.as_str().to_owned()),
+            ("game_directory", path_str(&self.game_dir)),
+            ("assets_root", path_str(&self.assets_dir)),
+            (
+                "game_assets",
+                path_str(&self.legacy_assets_dir(&assets_index_name)),
+            ),
+            ("assets_index_name", assets_index_name),
+            ("natives_directory", path_str(&self.natives_dir)),
+            ("library_directory", path_str(&self.libraries_dir)),
+            ("classpath_separator", separator.to_owned()),
+            ("classpath", classpath),
+            ("launcher_name", self.launcher_name.clone()),
+            ("launcher_version", self.launcher_version.clone()),
+            ("user_properties", "{}".to_owned()),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_owned(), value))
+        .collect();
+
+        if let Some(profile) = &self.profile {
+            values.extend(
+                profile
+                    .placeholders()
+                    .map(|(key, value)| (key.to_owned(), value)),
+            );
+        }
+        if let Some((width, height)) = self.resolution {
+            values.insert("resolution_width".to_owned(), width.to_string());
+            values.insert("resolution_height".to_owned(), height.to_string());
+        }
+        values.extend(self.placeholders.clone());
+        values
+    }
+
+    /// The full command line, starting with the `java` executable
+    pub fn build(&self) -> Vec<String> {
+        let manifest = self.manifest;
+        let mut context = self.context.clone();
+        if self.resolution.is_some() {
+            context
+                .features
+                .insert(feature::HAS_CUSTOM_RESOLUTION.to_owned(), true);
+        }
+        let values = self.placeholders();
+
+        let mut jvm: Vec<&str> = match &manifest.arguments {
+            Some(arguments) if !arguments.jvm.is_empty() => arguments.resolve_jvm(&context),
+            _ => context.resolve(&LEGACY_JVM_ARGUMENTS),
+        };
+        let mut game: Vec<&str> = match &manifest.arguments {
+            Some(arguments) if !arguments.game.is_empty() => arguments.resolve_game(&context),
+            _ => manifest
+                .minecraft_arguments
+                .as_deref()
+                .unwrap_or_default()
+                .split_whitespace()
+                .collect(),
+        };
+        if manifest.arguments.is_none() && self.resolution.is_some() {
+            game.extend([
+                "--width",
+                "${resolution_width}",
+                "--height",
+                "${resolution_height}",
+            ]);
+        }
+
+        let logging = manifest
+            .logging
+            .as_ref()
+            .and_then(|logging| logging.client.as_ref())
+            .map(|config| {
+                let path = self.assets_dir.join("log_configs").join(&config.file.id);
+                config.argument.replace("${path}", &path_str(&path))
+            });
+        if let Some(logging) = &logging {
+            jvm.push(logging);
+        }
+
+        let mut command = vec![path_str(&self.java)];
+        command.extend(
+            jvm.into_iter()
+                .map(|argument| substitute(argument, &values)),
+        );
+        command.push(manifest.main_class.clone());
+        command.extend(
+            game.into_iter()
+                .map(|argument| substitute(argument, &values)),
+        );
+        command
+    }
+
+    /// Where versions from before 1.7.3 expect their assets laid out by name
+    fn legacy_assets_dir(&self, assets_index_name: &str) -> PathBuf {
+        match assets_index_name {
+            "pre-1.6" => self.game_dir.join("resources"),
+            "legacy" => self.assets_dir.join("virtual").join("legacy"),
+            _ => self.assets_dir.clone(),
+        }
+    }
+}
+
+/// Replace every `${key}` with its value, leaving unknown placeholders as is
+fn substitute(argument: &str, values: &HashMap<String, String>) -> String {
+    let mut result = String::with_capacity(argument.len());
+    let mut rest = argument;
+    while let Some(start) = rest.find("${") {
+        let Some(length) = rest[start..].find('}') else {
+            break;
+        };
+        let key = &rest[start + 2..start + length];
+        result.push_str(&rest[..start]);
+        match values.get(key) {
+            Some(value) => result.push_str(value),
+            None => result.push_str(&rest[start..=start + length]),
+        }
+        rest = &rest[start + length + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+fn path_str(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Arch;
+    use serde_json::{Value, json};
+
+    const LEGACY_GAME_ARGUMENTS: &str = "--username ${auth_player_name} --version ${version_name} \
+        --gameDir ${game_directory} --assetsDir ${assets_root} --assetIndex ${assets_index_name} \
+        --uuid ${auth_uuid} --accessToken ${auth_access_token} --userType ${user_type} \
+        --versionType ${version_type}";
+
+    fn download(path: &str) -> Value {
+        json!({
+            "path": path,
+            "sha1": "0".repeat(40),
+            "size": 1,
+            "url": format!("https://libraries.minecraft.net/{path}"),
+        })
+    }
+
+    /// A version JSON with `extra` merged in, and a library only used on macOS
+    fn manifest(id: &str, extra: Value) -> VersionManifest {
+        let mut manifest = json!({
+            "id": id,
+            "type": "release",
+            "mainClass": "net.minecraft.client.main.Main",
+            "releaseTime": "2024-01-01T00:00:00+00:00",
+            "time": "2024-01-01T00:00:00+00:00",
+            "downloads": {
+                "client": {
+                    "sha1": "0".repeat(40),
+                    "size": 1,
+                    "url": "https://piston-data.mojang.com/v1/objects/client.jar",
+                },
+            },
+            "libraries": [
+                {
+                    "name": "com.example:a:1",
+                    "downloads": { "artifact": download("com/example/a/1/a-1.jar") },
+                },
+                {
+                    "name": "com.example:mac:1",
+                    "downloads": { "artifact": download("com/example/mac/1/mac-1.jar") },
+                    "rules": [{ "action": "allow", "os": { "name": "osx" } }],
+                },
+            ],
+        });
+        let object = manifest.as_object_mut().unwrap();
+        object.extend(extra.as_object().unwrap().clone());
+        crate::from_json(manifest.to_string().as_bytes()).unwrap()
+    }
+
+    fn spec(manifest: &VersionManifest, platform: Platform) -> LaunchSpec<'_> {
+        LaunchSpec::new(manifest, "/game")
+            .java("/usr/bin/java")
+            .platform(platform)
+            .profile(Profile::new(
+                "Steve",
+                "5627dd98e6be3c21b8a8e92344183641",
+                "token",
+            ))
+            .launcher("mcdl", "1.0")
+    }
+
+    fn owned(arguments: &[&str]) -> Vec<String> {
+        arguments
+            .iter()
+            .map(|&argument| argument.to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn builds_modern_arguments() {
+        let manifest = manifest(
+            "1.20.4",
+            json!({
+                "assetIndex": {
+                    "id": "12",
+                    "sha1": "0".repeat(40),
+                    "size": 1,
+                    "totalSize": 1,
+                    "url": "https://piston-meta.mojang.com/v1/packages/12.json",
+                },
+                "arguments": {
+                    "game": [
+                        "--username", "${auth_player_name}",
+                        "--version", "${version_name}",
+                        "--gameDir", "${game_directory}",
+                        "--assetsDir", "${assets_root}",
+                        "--assetIndex", "${assets_index_name}",
+                        "--uuid", "${auth_uuid}",
+                        "--accessToken", "${auth_access_token}",
+                        "--userType", "${user_type}",
+                        "--versionType", "${version_type}",
+                        {
+                            "rules": [{ "action": "allow", "features": { "is_demo_user": true } }],
+                            "value": "--demo",
+                        },
+                        {
+                            "rules": [{
+                                "action": "allow",
+                                "features": { "has_custom_resolution": true },
+                            }],
+                            "value": ["--width", "${resolution_width}", "--height", "${resolution_height}"],
+                        },
+                    ],
+                    "jvm": [
+                        {
+                            "rules": [{ "action": "allow", "os": { "name": "osx" } }],
+                            "value": ["-XstartOnFirstThread"],
+                        },
+                        {
+                            "rules": [{ "action": "allow", "os": { "arch": "x86" } }],
+                            "value": "-Xss1M",
+                        },
+                        "-Djava.library.path=${natives_directory}",
+                        "-Dminecraft.launcher.brand=${launcher_name}",
+                        "-Dminecraft.launcher.version=${launcher_version}",
+                        "-cp",
+                        "${classpath}",
+                    ],
+                },
+                "logging": {
+                    "client": {
+                        "argument": "-Dlog4j.configurationFile=${path}",
+                        "file": {
+                            "id": "client-1.12.xml",
+                            "sha1": "0".repeat(40),
+                            "size": 1,
+                            "url": "https://piston-data.mojang.com/v1/objects/client-1.12.xml",
+                        },
+                        "type": "log4j2-xml",
+                    },
+                },
+            }),
+        );
+        let command = spec(&manifest, Platform::new(OsName::Linux, Arch::X86_64))
+            .resolution(854, 480)
+            .build();
+        assert_eq!(
+            command,
+            owned(&[
+                "/usr/bin/java",
+                "-Djava.library.path=/game/versions/1.20.4/natives",
+                "-Dminecraft.launcher.brand=mcdl",
+                "-Dminecraft.launcher.version=1.0",
+                "-cp",
+                "/game/libraries/com/example/a/1/a-1.jar:/game/versions/1.20.4/1.20.4.jar",
+                "-Dlog4j.configurationFile=/game/assets/log_configs/client-1.12.xml",
+                "net.minecraft.client.main.Main",
+                "--username",
+                "Steve",
+                "--version",
+                "1.20.4",
+                "--gameDir",
+                "/game",
+                "--assetsDir",
+                "/game/assets",
+                "--assetIndex",
+                "12",
+                "--uuid",
+                "5627dd98e6be3c21b8a8e92344183641",
+                "--accessToken",
+                "token",
+                "--userType",
+                "msa",
+                "--versionType",
+                "release",
+                "--width",
+                "854",
+                "--height",
+                "480",
+            ])
+        );
+    }
+
+    #[test]
+    fn builds_legacy_arguments_on_macos() {
+        let manifest = manifest(
+            "1.12.2",
+            json!({ "assets": "1.12", "minecraftArguments": LEGACY_GAME_ARGUMENTS }),
+        );
+        let command = spec(&manifest, Platform::new(OsName::Osx, Arch::X86_64)).build();
+        assert_eq!(
+            command,
+            owned(&[
+                "/usr/bin/java",
+                "-XstartOnFirstThread",
+                "-Djava.library.path=/game/versions/1.12.2/natives",
+                "-Dminecraft.launcher.brand=mcdl",
+                "-Dminecraft.launcher.version=1.0",
+                "-cp",
+                "/game/libraries/com/example/a/1/a-1.jar:\
+                 /game/libraries/com/example/mac/1/mac-1.jar:\
+                 /game/versions/1.12.2/1.12.2.jar",
+                "net.minecraft.client.main.Main",
+                "--username",
+                "Steve",
+                "--version",
+                "1.12.2",
+                "--gameDir",
+                "/game",
+                "--assetsDir",
+                "/game/assets",
+                "--assetIndex",
+                "1.12",
+                "--uuid",
+                "5627dd98e6be3c21b8a8e92344183641",
+                "--accessToken",
+                "token",
+                "--userType",
+                "msa",
+                "--versionType",
+                "release",
+            ])
+        );
+    }
+
+    #[test]
+    fn builds_legacy_arguments_on_windows() {
+        let manifest = manifest(
+            "1.12.2",
+            json!({ "assets": "1.12", "minecraftArguments": LEGACY_GAME_ARGUMENTS }),
+        );
+        let platform = Platform::new(OsName::Windows, Arch::X86).with_os_version("10.0");
+        let command = spec(&manifest, platform).resolution(854, 480).build();
+        let main_class = command
+            .iter()
+            .position(|argument| *argument == manifest.main_class)
+            .unwrap();
+        let jvm = &command[1..main_class];
+        assert_eq!(
+            jvm,
+            owned(&[
+                "-XX:HeapDumpPath=MojangTricksIntelDriversForPerformance_javaw.exe_minecraft.exe.heapdump",
+                "-Dos.name=Windows 10",
+                "-Dos.version=10.0",
+                "-Xss1M",
+                "-Djava.library.path=/game/versions/1.12.2/natives",
+                "-Dminecraft.launcher.brand=mcdl",
+                "-Dminecraft.launcher.version=1.0",
+                "-cp",
+                "/game/libraries/com/example/a/1/a-1.jar;/game/versions/1.12.2/1.12.2.jar",
+            ])
+        );
+        assert_eq!(
+            command[command.len() - 4..],
+            owned(&["--width", "854", "--height", "480"])
+        );
+    }
+
+    #[test]
+    fn substitutes_known_placeholders_only() {
+        let values = HashMap::from([("name".to_owned(), "Steve".to_owned())]);
+        let cases = [
+            ("${name}", "Steve"),
+            ("--user=${name}${name}", "--user=SteveSteve"),
+            ("${unknown} ${name}", "${unknown} Steve"),
+            ("${name} ${unterminated", "Steve ${unterminated"),
+            ("$name {name}", "$name {name}"),
+            ("", ""),
+        ];
+        for (argument, expected) in cases {
+            assert_eq!(substitute(argument, &values), expected, "{argument}");
+        }
+    }
+}
@@ -1,11 +1,65 @@
+mod assets;
+mod bulk;
+mod cache;
+mod client;
+mod error;
+mod fs;
+mod install;
+mod launch;
+mod mirror;
+mod natives;
+mod platform;
+mod profile;
+mod progress;
+mod query;
+mod retry;
+mod rules;
+mod runtime;
+mod store;
+mod timestamp;
+mod transport;
+mod verify;
+mod version;
+
+pub use assets::{AssetIndex, AssetObject};
+pub use bulk::DownloadJob;
+pub use cache::ManifestCache;
+pub use client::{Mcdl, McdlBuilder};
+pub use error::{Error, Mismatch, Result};
+pub use install::Installer;
+pub use launch::LaunchSpec;
+pub use mirror::Mirrors;
+pub use platform::{Arch, OsName, Platform};
+pub use profile::{Profile, offline_uuid};
+pub use progress::{Progress, ProgressEvent};
+pub use query::{LATEST, LATEST_SNAPSHOT, VersionQuery};
+pub use retry::RetryPolicy;
+pub use rules::{RuleContext, feature};
+pub use runtime::{
+    JavaRuntime, JavaRuntimeIndex, JavaRuntimeManifest, RuntimeAvailability, RuntimeFile,
+    RuntimeFileDownloads, RuntimeVersion,
+};
+pub use store::{BlobStore, GcStats, LinkMode};
+pub use transport::{
+    BoxFuture, ByteStream, Conditional, DirectoryTransport, MemoryTransport, ReqwestTransport,
+    Transport, Validators,
+};
+pub use version::{ReleaseNumber, VersionForm, VersionId};
+
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
 use futures_core::Stream;
+use regex::Regex;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{LazyLock, Mutex};
 use url::Url;
 
-const MANIFEST_URL: &str = "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
+pub(crate) const MANIFEST_URL: &str =
+    "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct LatestReleases {
@@ -29,7 +83,9 @@ pub struct VersionRelease {
     #[serde(rename = "type")]
     pub kind: ReleaseKind,
     pub url: Url,
+    #[serde(with = "timestamp")]
     pub time: DateTime<Utc>,
+    #[serde(with = "timestamp")]
     pub release_time: DateTime<Utc>,
     pub sha1: String,
     pub compliance_level: u8,
@@ -41,20 +97,126 @@ pub struct RootManifest {
     pub versions: Vec<VersionRelease>,
 }
 
-// many fields omitted
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
 pub struct VersionManifest {
-    pub downloads: VersionDownloads,
+    /// Modern launch arguments, present since 1.13
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub arguments: Option<Arguments>,
+    /// Space separated game arguments used before 1.13
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub minecraft_arguments: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub asset_index: Option<AssetIndexInfo>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub assets: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub compliance_level: Option<u8>,
+    /// Missing on manifests that inherit their jar from a parent version
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub downloads: Option<VersionDownloads>,
     pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub java_version: Option<JavaVersion>,
     pub libraries: Vec<Library>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub logging: Option<Logging>,
+    pub main_class: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub minimum_launcher_version: Option<u32>,
+    #[serde(with = "timestamp")]
+    pub release_time: DateTime<Utc>,
+    #[serde(with = "timestamp")]
+    pub time: DateTime<Utc>,
+    #[serde(rename = "type")]
+    pub kind: ReleaseKind,
+    /// Id of a parent version this one extends, used by mod loaders
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub inherits_from: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Arguments {
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub game: Vec<Argument>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub jvm: Vec<Argument>,
+}
+
+/// A launch argument, possibly containing `${...}` placeholders
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Argument {
+    Plain(String),
+    Conditional {
+        rules: Vec<Rule>,
+        value: ArgumentValue,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum ArgumentValue {
+    Single(String),
+    Many(Vec<String>),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetIndexInfo {
+    pub id: String,
+    pub sha1: String,
+    pub size: u64,
+    /// Size of the index plus every object it lists
+    pub total_size: u64,
+    pub url: Url,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct JavaVersion {
+    /// Name of the Mojang Java runtime, e.g. `java-runtime-gamma`
+    pub component: String,
+    pub major_version: u32,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Logging {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub client: Option<LoggingConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LoggingConfig {
+    /// JVM argument with a `${path}` placeholder for the config file
+    pub argument: String,
+    pub file: LoggingFile,
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LoggingFile {
+    pub id: String,
+    pub sha1: String,
+    pub size: u64,
+    pub url: Url,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct VersionDownloads {
     pub client: DownloadInfo,
-    pub client_mappings: DownloadInfo,
-    pub server: DownloadInfo,
-    pub server_mappings: DownloadInfo,
+    /// Only published since 19w36a
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub client_mappings: Option<DownloadInfo>,
+    /// Missing for early alpha and beta versions
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub server: Option<DownloadInfo>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub server_mappings: Option<DownloadInfo>,
+    /// Any other downloads, such as `windows_server` on some old versions
+    #[serde(flatten)]
+    pub other: HashMap<String, DownloadInfo>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
@@ -66,6 +228,8 @@ pub struct DownloadInfo {
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct Library {
+    /// Empty on libraries of mod loader manifests, which give a [`url`](Self::url)
+    #[serde(skip_serializing_if = "LibraryDownloads::is_empty", default)]
     pub downloads: LibraryDownloads,
     #[serde(skip_serializing_if = "LibraryExtractInstructions::is_empty", default)]
     pub extract: LibraryExtractInstructions,
@@ -74,6 +238,10 @@ pub struct Library {
     pub natives: HashMap<OsName, String>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub rules: Vec<Rule>,
+    /// Maven repository to resolve [`name`](Self::name) against, used by mod
+    /// loaders instead of `downloads`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub url: Option<Url>,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
@@ -81,9 +249,11 @@ pub struct LibraryExtractInstructions {
     pub exclude: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct LibraryDownloads {
-    pub artifact: LibraryDownload,
+    /// Missing on old natives-only libraries, which only have classifiers
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub artifact: Option<LibraryDownload>,
     #[serde(skip_serializing_if = "HashMap::is_empty", default)]
     pub classifiers: HashMap<String, LibraryDownload>,
 }
@@ -96,18 +266,14 @@ pub struct LibraryDownload {
     pub url: Url,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
-#[serde(rename_all = "lowercase")]
-pub enum OsName {
-    Linux,
-    Windows,
-    Osx,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct Rule {
     pub action: RuleAction,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
     pub os: Option<OsRule>,
+    /// Launcher features such as `is_demo_user` that must match for the rule to apply
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub features: HashMap<String, bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
@@ -117,86 +283,159 @@ pub enum RuleAction {
     Disallow,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct OsRule {
-    pub name: OsName,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub name: Option<OsName>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub arch: Option<Arch>,
+    /// Regex matched against the OS version
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub version: Option<String>,
+}
+
+impl ReleaseKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Snapshot => "snapshot",
+            Self::Release => "release",
+            Self::OldBeta => "old_beta",
+            Self::OldAlpha => "old_alpha",
+        }
+    }
+}
+
+/// A file hosted by Mojang whose digest and size are known up front
+pub trait RemoteFile {
+    fn url(&self) -> Cow<'_, Url>;
+    fn sha1(&self) -> &str;
+    fn size(&self) -> u64;
 }
 
 impl RootManifest {
-    pub async fn fetch() -> reqwest::Result<Self> {
-        Self::fetch_from_url(MANIFEST_URL).await
+    pub async fn fetch() -> Result<Self> {
+        Mcdl::shared().fetch_root_manifest().await
+    }
+
+    pub async fn fetch_from_url(url: &str) -> Result<Self> {
+        Mcdl::shared().fetch_root_manifest_from_url(url).await
     }
 
-    pub async fn fetch_from_url(url: &str) -> reqwest::Result<Self> {
-        reqwest::get(url).await?.error_for_status()?.json().await
+    pub fn version(&self, id: &str) -> Result<&VersionRelease> {
+        self.versions
+            .iter()
+            .find(|version| version.id == id)
+            .ok_or_else(|| Error::UnknownVersion(id.to_owned()))
     }
 }
 
 impl VersionRelease {
-    pub async fn fetch_manifest(&self) -> reqwest::Result<VersionManifest> {
-        reqwest::get(self.url.clone())
-            .await?
-            .error_for_status()?
-            .json()
-            .await
+    pub async fn fetch_manifest(&self) -> Result<VersionManifest> {
+        Mcdl::shared().fetch_version_manifest(self).await
     }
 }
 
 impl DownloadInfo {
-    pub async fn download(&self) -> reqwest::Result<Bytes> {
-        reqwest::get(self.url.clone())
-            .await?
-            .error_for_status()?
-            .bytes()
-            .await
+    pub async fn download(&self) -> Result<Bytes> {
+        Mcdl::shared().download(self).await
     }
 
-    pub async fn download_as_stream(
-        &self,
-    ) -> reqwest::Result<impl Stream<Item = reqwest::Result<Bytes>>> {
-        Ok(reqwest::get(self.url.clone())
-            .await?
-            .error_for_status()?
-            .bytes_stream())
-    }
-
-    pub async fn download_as_string(&self) -> reqwest::Result<String> {
-        reqwest::get(self.url.clone())
-            .await?
-            .error_for_status()?
-            .text()
-            .await
+    pub async fn download_as_stream(&self) -> Result<impl Stream<Item = Result<Bytes>>> {
+        Mcdl::shared().download_as_stream(self).await
+    }
+
+    pub async fn download_as_string(&self) -> Result<String> {
+        Mcdl::shared().download_as_string(self).await
     }
 }
 
-impl OsName {
-    pub fn current() -> Self {
-        #[cfg(target_os = "windows")]
-        let current = Self::Windows;
-        #[cfg(target_os = "linux")]
-        let current = Self::Linux;
-        #[cfg(target_os = "macos")]
-        let current = Self::Osx;
+impl RemoteFile for DownloadInfo {
+    fn url(&self) -> Cow<'_, Url> {
+        Cow::Borrowed(&self.url)
+    }
 
-        current
+    fn sha1(&self) -> &str {
+        &self.sha1
     }
 
-    pub fn is_current(&self) -> bool {
-        self == &Self::current()
+    fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+impl RemoteFile for AssetIndexInfo {
+    fn url(&self) -> Cow<'_, Url> {
+        Cow::Borrowed(&self.url)
+    }
+
+    fn sha1(&self) -> &str {
+        &self.sha1
+    }
+
+    fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+impl RemoteFile for LoggingFile {
+    fn url(&self) -> Cow<'_, Url> {
+        Cow::Borrowed(&self.url)
+    }
+
+    fn sha1(&self) -> &str {
+        &self.sha1
+    }
+
+    fn size(&self) -> u64 {
+        self.size
     }
 }
 
 impl OsRule {
     pub fn allow(&self) -> bool {
-        self.name.is_current()
+        self.allows(&Platform::current())
+    }
+
+    /// Whether every condition matches `platform`. A version pattern never
+    /// matches a platform without a known OS version, and neither does one
+    /// that is not a valid regex.
+    pub fn allows(&self, platform: &Platform) -> bool {
+        self.name.as_ref().is_none_or(|name| name.matches(platform))
+            && self.arch.as_ref().is_none_or(|arch| arch == &platform.arch)
+            && self.version.as_ref().is_none_or(|pattern| {
+                platform
+                    .os_version
+                    .as_deref()
+                    .is_some_and(|version| os_version_matches(pattern, version))
+            })
     }
 }
 
+/// Match against an `os.version` pattern, compiling each pattern only once
+/// since the same few are evaluated for every library and argument
+fn os_version_matches(pattern: &str, version: &str) -> bool {
+    static PATTERNS: LazyLock<Mutex<HashMap<String, Option<Regex>>>> =
+        LazyLock::new(Mutex::default);
+    let regex = PATTERNS
+        .lock()
+        .unwrap()
+        .entry(pattern.to_owned())
+        .or_insert_with(|| Regex::new(pattern).ok())
+        .clone();
+    regex.is_some_and(|regex| regex.is_match(version))
+}
+
 impl Rule {
     pub fn allow(&self) -> bool {
+        self.allows(&Platform::current())
+    }
+
+    /// Evaluate this rule on its own, with every launcher feature off
+    pub fn allows(&self, platform: &Platform) -> bool {
+        let applies = self.applies(&RuleContext::new(platform.clone()));
         match self.action {
-            RuleAction::Allow => self.os.as_ref().map_or(true, |os| os.allow()),
-            RuleAction::Disallow => self.os.as_ref().map_or(false, |os| !os.allow()),
+            RuleAction::Allow => applies,
+            RuleAction::Disallow => !applies,
         }
     }
 }
@@ -204,82 +443,79 @@ impl Rule {
 impl Library {
     pub async fn download(
         &self,
-    ) -> reqwest::Result<Option<((String, Bytes), Option<(String, Bytes)>)>> {
-        if !self.rules.iter().all(Rule::allow) {
-            return Ok(None);
-        }
-        let artifact = (
-            self.downloads.artifact.path.clone(),
-            self.downloads.artifact.download().await?,
-        );
-        let natives = if let Some(natives_key) = self.natives.get(&OsName::current()) {
-            if let Some(natives_artifact) = self.downloads.classifiers.get(natives_key) {
-                Some((
-                    natives_artifact.path.clone(),
-                    natives_artifact.download().await?,
-                ))
-            } else {
-                None
-            }
-        } else {
-            None
-        };
-        Ok(Some((artifact, natives)))
+    ) -> Result<Option<(Option<(String, Bytes)>, Option<(String, Bytes)>)>> {
+        Mcdl::shared().download_library(self).await
     }
 
     pub async fn download_as_stream(
         &self,
-    ) -> reqwest::Result<
+    ) -> Result<
         Option<(
-            (String, impl Stream<Item = reqwest::Result<Bytes>>),
-            Option<(String, impl Stream<Item = reqwest::Result<Bytes>>)>,
+            Option<(String, impl Stream<Item = Result<Bytes>>)>,
+            Option<(String, impl Stream<Item = Result<Bytes>>)>,
         )>,
     > {
-        if !self.rules.iter().all(Rule::allow) {
-            return Ok(None);
-        }
-        let artifact = (
-            self.downloads.artifact.path.clone(),
-            self.downloads.artifact.download_as_stream().await?,
-        );
-        let native = if let Some(native_artifact) = self.native() {
-            Some((
-                native_artifact.path.clone(),
-                native_artifact.download_as_stream().await?,
-            ))
-        } else {
-            None
-        };
-        Ok(Some((artifact, native)))
+        Mcdl::shared().download_library_as_stream(self).await
+    }
+
+    /// Whether the library's rules allow it on the current OS
+    pub fn is_allowed(&self) -> bool {
+        self.is_allowed_on(&Platform::current())
     }
 
-    pub fn artifact(&self) -> &LibraryDownload {
-        &self.downloads.artifact
+    pub fn is_allowed_on(&self, platform: &Platform) -> bool {
+        RuleContext::new(platform.clone()).allows(&self.rules)
+    }
+
+    pub fn artifact(&self) -> Option<&LibraryDownload> {
+        self.downloads.artifact.as_ref()
     }
 
     pub fn native(&self) -> Option<&LibraryDownload> {
-        self.natives
-            .get(&OsName::current())
-            .and_then(|natives_key| self.downloads.classifiers.get(natives_key))
+        self.native_for(&Platform::current())
+    }
+
+    /// The classifier jar holding this library's native code for `platform`
+    pub fn native_for(&self, platform: &Platform) -> Option<&LibraryDownload> {
+        let natives_key = platform
+            .qualified_os()
+            .and_then(|os| self.natives.get(&os))
+            .or_else(|| self.natives.get(&platform.os.family()))?;
+        let natives_key = natives_key.replace("${arch}", platform.arch.bits());
+        self.downloads.classifiers.get(&natives_key)
+    }
+
+    /// Download the natives for `platform` and unpack them into `natives_dir`.
+    ///
+    /// See [`Mcdl::extract_natives`].
+    pub async fn extract_natives(&self, platform: &Platform, natives_dir: &Path) -> Result<bool> {
+        Mcdl::shared()
+            .extract_natives(self, platform, natives_dir)
+            .await
     }
 }
 
 impl LibraryDownload {
-    pub async fn download(&self) -> reqwest::Result<Bytes> {
-        reqwest::get(self.url.clone())
-            .await?
-            .error_for_status()?
-            .bytes()
-            .await
+    pub async fn download(&self) -> Result<Bytes> {
+        Mcdl::shared().download(self).await
     }
 
-    pub async fn download_as_stream(
-        &self,
-    ) -> reqwest::Result<impl Stream<Item = reqwest::Result<Bytes>>> {
-        Ok(reqwest::get(self.url.clone())
-            .await?
-            .error_for_status()?
-            .bytes_stream())
+    pub async fn download_as_stream(&self) -> Result<impl Stream<Item = Result<Bytes>>> {
+        Mcdl::shared().download_as_stream(self).await
+    }
+}
+
+impl RemoteFile for LibraryDownload {
+    fn url(&self) -> Cow<'_, Url> {
+        Cow::Borrowed(&self.url)
+    }
+
+    fn sha1(&self) -> &str {
+        &self.sha1
+    }
+
+    fn size(&self) -> u64 {
+        self.size
     }
 }
 
@@ -289,6 +525,19 @@ impl LibraryExtractInstructions {
     }
 }
 
+impl LibraryDownloads {
+    pub fn is_empty(&self) -> bool {
+        self.artifact.is_none() && self.classifiers.is_empty()
+    }
+}
+
+/// Deserialize a JSON body, keeping track of where decoding failed
+pub(crate) fn from_json<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    Ok(serde_path_to_error::deserialize(
+        &mut serde_json::Deserializer::from_slice(bytes),
+    )?)
+}
+
 /// Remove array brackets and count them
 fn remove_brackets(line: &str) -> (&str, usize) {
     let mut result = line;
@@ -370,20 +619,24 @@ pub fn convert_mappings(mappings: &str) -> String {
                 continue;
             }
 
-            let mut method_type = method_parts[0].split(':').last().unwrap_or("").to_string();
+            let mut method_type = method_parts[0]
+                .split(':')
+                .next_back()
+                .unwrap_or("")
+                .to_string();
             let method_name = method_parts[1];
 
             if method_name.contains('(') && method_name.contains(')') {
                 // Process function
                 let function_name = method_name.split('(').next().unwrap_or("");
-                let variables_str = if let Some(v) = method_name.split('(').last() {
+                let variables_str = if let Some(v) = method_name.split('(').next_back() {
                     v.split(')').next().unwrap_or("")
                 } else {
                     ""
                 };
 
                 let (method_type_clean, array_length_type) = remove_brackets(&method_type);
-                method_type = remap_file_path(&method_type_clean);
+                method_type = remap_file_path(method_type_clean);
 
                 // Get obfuscated class name if available
                 if let Some(obf_class) = file_name.get(&method_type) {
@@ -412,7 +665,7 @@ pub fn convert_mappings(mappings: &str) -> String {
 
                     for variable in variables {
                         let (var_clean, array_count) = remove_brackets(variable);
-                        let mut remapped = remap_file_path(&var_clean);
+                        let mut remapped = remap_file_path(var_clean);
 
                         // Get obfuscated class name if available
                         if file_name.contains_key(&remapped) {
@@ -464,3 +717,171 @@ pub fn convert_mappings(mappings: &str) -> String {
 
     output
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    /// 1.20.4 as published, with most libraries and arguments cut
+    const VANILLA: &str = r#"{
+        "arguments": {
+            "game": [
+                "--username", "${auth_player_name}",
+                "--version", "${version_name}",
+                {
+                    "rules": [{ "action": "allow", "features": { "is_demo_user": true } }],
+                    "value": "--demo"
+                },
+                {
+                    "rules": [{ "action": "allow", "features": { "has_custom_resolution": true } }],
+                    "value": ["--width", "${resolution_width}", "--height", "${resolution_height}"]
+                }
+            ],
+            "jvm": [
+                {
+                    "rules": [{ "action": "allow", "os": { "name": "osx" } }],
+                    "value": ["-XstartOnFirstThread"]
+                },
+                {
+                    "rules": [{ "action": "allow", "os": { "name": "windows" } }],
+                    "value": "-XX:HeapDumpPath=MojangTricksIntelDriversForPerformance_javaw.exe_minecraft.exe.heapdump"
+                },
+                {
+                    "rules": [{ "action": "allow", "os": { "arch": "x86" } }],
+                    "value": "-Xss1M"
+                },
+                "-Djava.library.path=${natives_directory}",
+                "-cp",
+                "${classpath}"
+            ]
+        },
+        "assetIndex": {
+            "id": "12",
+            "sha1": "b8a1d1c2c5e7a8f4b7d3e8a5a7e0c1ef29e4df2b",
+            "size": 433948,
+            "totalSize": 629457574,
+            "url": "https://piston-meta.mojang.com/v1/packages/b8a1d1c2c5e7a8f4b7d3e8a5a7e0c1ef29e4df2b/12.json"
+        },
+        "assets": "12",
+        "complianceLevel": 1,
+        "downloads": {
+            "client": {
+                "sha1": "fd19469fed4a4b4c15b2d5133985f0e3e7816a8a",
+                "size": 24445539,
+                "url": "https://piston-data.mojang.com/v1/objects/fd19469fed4a4b4c15b2d5133985f0e3e7816a8a/client.jar"
+            },
+            "client_mappings": {
+                "sha1": "be76ecc174ea25580bdc9bf335481a5192d9f3b7",
+                "size": 9167810,
+                "url": "https://piston-data.mojang.com/v1/objects/be76ecc174ea25580bdc9bf335481a5192d9f3b7/client.txt"
+            },
+            "server": {
+                "sha1": "8dd1a28015f51b1803213892b50b7b4fc76e594d",
+                "size": 49150256,
+                "url": "https://piston-data.mojang.com/v1/objects/8dd1a28015f51b1803213892b50b7b4fc76e594d/server.jar"
+            },
+            "server_mappings": {
+                "sha1": "c1cafe916dd8b58ed1fe0564fc8f786885224e62",
+                "size": 7283803,
+                "url": "https://piston-data.mojang.com/v1/objects/c1cafe916dd8b58ed1fe0564fc8f786885224e62/server.txt"
+            }
+        },
+        "id": "1.20.4",
+        "javaVersion": { "component": "java-runtime-gamma", "majorVersion": 17 },
+        "libraries": [
+            {
+                "downloads": {
+                    "artifact": {
+                        "path": "com/mojang/blocklist/1.0.10/blocklist-1.0.10.jar",
+                        "sha1": "5c685c5ffa94c4cd39496c7184c1d122e515ecef",
+                        "size": 964,
+                        "url": "https://libraries.minecraft.net/com/mojang/blocklist/1.0.10/blocklist-1.0.10.jar"
+                    }
+                },
+                "name": "com.mojang:blocklist:1.0.10"
+            },
+            {
+                "downloads": {
+                    "artifact": {
+                        "path": "org/lwjgl/lwjgl/3.3.2/lwjgl-3.3.2-natives-macos-arm64.jar",
+                        "sha1": "b3b4b8b0ac5f2e5e4d2b7a4f6e0e0cf1b1e2d3a4",
+                        "size": 42681,
+                        "url": "https://libraries.minecraft.net/org/lwjgl/lwjgl/3.3.2/lwjgl-3.3.2-natives-macos-arm64.jar"
+                    }
+                },
+                "name": "org.lwjgl:lwjgl:3.3.2:natives-macos-arm64",
+                "rules": [{ "action": "allow", "os": { "name": "osx" } }]
+            }
+        ],
+        "logging": {
+            "client": {
+                "argument": "-Dlog4j.configurationFile=${path}",
+                "file": {
+                    "id": "client-1.12.xml",
+                    "sha1": "bd65e7d2e3c237be76cfbef4c2405033d7f91521",
+                    "size": 888,
+                    "url": "https://piston-data.mojang.com/v1/objects/bd65e7d2e3c237be76cfbef4c2405033d7f91521/client-1.12.xml"
+                },
+                "type": "log4j2-xml"
+            }
+        },
+        "mainClass": "net.minecraft.client.main.Main",
+        "minimumLauncherVersion": 21,
+        "releaseTime": "2023-12-07T12:56:20+00:00",
+        "time": "2023-12-07T12:56:20+00:00",
+        "type": "release"
+    }"#;
+
+    /// A Fabric loader profile, which inherits everything else from 1.20.4
+    const FABRIC: &str = r#"{
+        "id": "fabric-loader-0.15.3-1.20.4",
+        "inheritsFrom": "1.20.4",
+        "releaseTime": "2023-12-23T21:05:27+0000",
+        "time": "2023-12-23T21:05:27+0000",
+        "type": "release",
+        "mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient",
+        "arguments": {
+            "jvm": ["-DFabricMcEmu= net.minecraft.client.main.Main "]
+        },
+        "libraries": [
+            { "name": "org.ow2.asm:asm:9.6", "url": "https://maven.fabricmc.net/" },
+            { "name": "net.fabricmc:intermediary:1.20.4", "url": "https://maven.fabricmc.net/" },
+            { "name": "net.fabricmc:fabric-loader:0.15.3", "url": "https://maven.fabricmc.net/" }
+        ]
+    }"#;
+
+    fn round_trip(json: &str) -> VersionManifest {
+        let manifest: VersionManifest = from_json(json.as_bytes()).unwrap();
+        let original: Value = serde_json::from_str(json).unwrap();
+        assert_eq!(serde_json::to_value(&manifest).unwrap(), original);
+        manifest
+    }
+
+    #[test]
+    fn round_trips_vanilla_manifests() {
+        let manifest = round_trip(VANILLA);
+        assert_eq!(manifest.java_version.unwrap().major_version, 17);
+        assert_eq!(manifest.libraries.len(), 2);
+        assert!(manifest.downloads.unwrap().other.is_empty());
+    }
+
+    #[test]
+    fn round_trips_inheriting_manifests() {
+        // Fabric leaves the colon out of offsets, which come back the way Mojang writes them
+        let manifest = round_trip(&FABRIC.replace("+0000", "+00:00"));
+        assert_eq!(
+            from_json::<VersionManifest>(FABRIC.as_bytes()).unwrap(),
+            manifest
+        );
+        assert_eq!(manifest.inherits_from.as_deref(), Some("1.20.4"));
+        assert_eq!(manifest.downloads, None);
+        let library = &manifest.libraries[0];
+        assert!(library.downloads.is_empty());
+        assert_eq!(library.artifact(), None);
+        assert_eq!(
+            library.url.as_ref().map(Url::as_str),
+            Some("https://maven.fabricmc.net/")
+        );
+    }
+}
@@ -0,0 +1,454 @@
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use mcdl::{
+    DownloadInfo, Error, Installer, Mcdl, Mirrors, ProgressEvent, ReleaseKind, RootManifest,
+    VersionManifest, VersionRelease,
+};
+use serde_json::json;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Download Minecraft versions, libraries and assets from Mojang's servers
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    after_help = "Exit codes:
+  0  success
+  1  I/O or other error
+  2  invalid arguments
+  3  unknown version, or no such download for it
+  4  network error or unexpected HTTP status
+  5  downloaded file failed its integrity check
+  6  malformed manifest
+  7  some files of a bulk download failed"
+)]
+struct Cli {
+    #[command(flatten)]
+    options: Options,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Args)]
+struct Options {
+    /// Print machine-readable JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+    /// Cache manifests in this directory
+    #[arg(long, global = true, value_name = "DIR")]
+    cache_dir: Option<PathBuf>,
+    /// Keep downloads in a content-addressed store in this directory
+    #[arg(long, global = true, value_name = "DIR")]
+    store_dir: Option<PathBuf>,
+    /// Fetch from a mirror, as `HOST=BASE` or `PREFIX=REPLACEMENT`; repeat for fallbacks
+    #[arg(long, global = true, value_name = "RULE", value_parser = parse_mirror)]
+    mirror: Vec<(String, String)>,
+    /// Fetch the version and Java runtime indexes from mirrors too, trusting them for every sha1
+    #[arg(long, global = true)]
+    mirror_indexes: bool,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// List published versions, oldest first
+    List {
+        /// Only versions of this kind; repeat for several
+        #[arg(long, value_enum)]
+        kind: Vec<Kind>,
+        /// Only versions released on or after this date, as YYYY-MM-DD
+        #[arg(long, value_parser = parse_date)]
+        since: Option<NaiveDate>,
+        /// Only versions released on or before this date, as YYYY-MM-DD
+        #[arg(long, value_parser = parse_date)]
+        until: Option<NaiveDate>,
+        /// Only versions from this one to that one, as `FROM..TO`
+        #[arg(long, value_name = "FROM..TO", value_parser = parse_range)]
+        between: Option<(String, String)>,
+        /// Only versions with this compliance level; repeat for several
+        #[arg(long, value_name = "LEVEL")]
+        compliance_level: Vec<u8>,
+    },
+    /// Show details about a version
+    Info {
+        /// A version id, `latest` or `latest-snapshot`
+        version: String,
+    },
+    /// Download a single file of a version
+    Download {
+        #[arg(value_enum)]
+        file: File,
+        /// A version id, `latest` or `latest-snapshot`
+        version: String,
+        /// Where to save the file, by default `<version>-<file>` in the current directory
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Install a version with its libraries and assets into a game directory
+    Install {
+        /// A version id, `latest` or `latest-snapshot`
+        version: String,
+        dir: PathBuf,
+    },
+    /// Work with obfuscation mappings
+    #[command(subcommand)]
+    Mappings(Mappings),
+}
+
+#[derive(Debug, Subcommand)]
+enum Mappings {
+    /// Convert ProGuard mappings as published by Mojang
+    Convert {
+        /// Mappings to read, `-` for stdin
+        input: PathBuf,
+        /// Where to write the result, stdout by default
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Kind {
+    Release,
+    Snapshot,
+    OldBeta,
+    OldAlpha,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum File {
+    Client,
+    Server,
+    /// The client mappings
+    Mappings,
+    ClientMappings,
+    ServerMappings,
+}
+
+/// What went wrong, with the exit code and a stable name for JSON output
+struct Failure {
+    code: u8,
+    kind: &'static str,
+    message: String,
+}
+
+impl From<Error> for Failure {
+    fn from(err: Error) -> Self {
+        let (code, kind) = match &err {
+            Error::UnknownVersion(_) | Error::UnknownRuntime { .. } => (3, "not_found"),
+            Error::Status { .. } | Error::Transport(_) => (4, "network"),
+            Error::Integrity { .. } => (5, "integrity"),
+            Error::Json { .. } | Error::InvalidSha1(_) | Error::UnsafePath(_) => (6, "malformed"),
+            Error::Bulk { .. } => (7, "partial"),
+            Error::InvalidUrl(_) | Error::Io(_) => (1, "io"),
+        };
+        let mut message = err.to_string();
+        if let Error::Bulk { failures, .. } = &err {
+            for (job, err) in failures {
+                message.push_str(&format!("\n  {}: {err}", job.dest.display()));
+            }
+        }
+        Self {
+            code,
+            kind,
+            message,
+        }
+    }
+}
+
+impl From<std::io::Error> for Failure {
+    fn from(err: std::io::Error) -> Self {
+        Error::from(err).into()
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let json = cli.options.json;
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => {
+            if json {
+                let error = json!({ "error": failure.kind, "message": failure.message });
+                eprintln!("{error}");
+            } else {
+                eprintln!("error: {}", failure.message);
+            }
+            ExitCode::from(failure.code)
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Failure> {
+    let options = &cli.options;
+    let mcdl = client(options)?;
+    match cli.command {
+        Command::List {
+            kind,
+            since,
+            until,
+            between,
+            compliance_level,
+        } => {
+            let root = mcdl.fetch_root_manifest().await?;
+            let mut query = root.query();
+            for kind in kind {
+                query = query.kind(kind.into());
+            }
+            for level in compliance_level {
+                query = query.compliance_level(level);
+            }
+            if let Some(since) = since {
+                query = query.since(since.and_time(NaiveTime::MIN).and_utc());
+            }
+            if let Some(end) =
+                until.and_then(|until| until.and_hms_nano_opt(23, 59, 59, 999_999_999))
+            {
+                query = query.until(end.and_utc());
+            }
+            if let Some((from, to)) = &between {
+                query = query.between(from, to);
+            }
+            let versions = query.run()?;
+            if options.json {
+                print_json(&versions)?;
+            } else {
+                for version in versions {
+                    println!(
+                        "{:<24} {:<10} {}",
+                        version.id,
+                        version.kind.as_str(),
+                        version.release_time.date_naive()
+                    );
+                }
+            }
+        }
+        Command::Info { version } => {
+            let (release, manifest) = fetch(&mcdl, &version).await?;
+            info(release, &manifest, options.json)?;
+        }
+        Command::Download {
+            file,
+            version,
+            output,
+        } => {
+            let (_, manifest) = fetch(&mcdl, &version).await?;
+            let Some(download) = file.select(&manifest) else {
+                return Err(Failure {
+                    code: 3,
+                    kind: "not_found",
+                    message: format!("version `{version}` has no {} download", file.name()),
+                });
+            };
+            let output = output.unwrap_or_else(|| {
+                let extension = if file.is_mappings() { "txt" } else { "jar" };
+                PathBuf::from(format!("{version}-{}.{extension}", file.name()))
+            });
+            mcdl.download_to(download, &output).await?;
+            if options.json {
+                print_json(
+                    &json!({ "path": output, "sha1": download.sha1, "size": download.size }),
+                )?;
+            } else {
+                println!("{}", output.display());
+            }
+        }
+        Command::Install { version, dir } => {
+            let mcdl = if options.json {
+                mcdl
+            } else {
+                with_progress(mcdl)
+            };
+            let manifest = Installer::with_mcdl(mcdl, &dir).install(&version).await?;
+            if options.json {
+                print_json(&json!({ "id": manifest.id, "dir": dir }))?;
+            } else {
+                println!("Installed {} into {}", manifest.id, dir.display());
+            }
+        }
+        Command::Mappings(Mappings::Convert { input, output }) => {
+            let mut mappings = String::new();
+            if input.as_os_str() == "-" {
+                std::io::stdin().read_to_string(&mut mappings)?;
+            } else {
+                mappings = std::fs::read_to_string(&input)?;
+            }
+            let converted = mcdl::convert_mappings(&mappings);
+            match output {
+                Some(path) => std::fs::write(path, converted)?,
+                None => std::io::stdout().write_all(converted.as_bytes())?,
+            }
+        }
+    }
+    Ok(())
+}
+
+fn client(options: &Options) -> Result<Mcdl, Failure> {
+    let mut builder = Mcdl::builder();
+    if let Some(dir) = &options.cache_dir {
+        builder = builder.cache_dir(dir);
+    }
+    if let Some(dir) = &options.store_dir {
+        builder = builder.store_dir(dir);
+    }
+    let mut mirrors = Mirrors::new().indexes(options.mirror_indexes);
+    for (from, to) in &options.mirror {
+        mirrors = if from.contains("://") {
+            mirrors.prefix(from, to)
+        } else {
+            mirrors.host(from, to)
+        };
+    }
+    Ok(builder.mirrors(mirrors).build()?)
+}
+
+/// Print a summary of each bulk download to stderr
+fn with_progress(mcdl: Mcdl) -> Mcdl {
+    mcdl.with_progress(|event: ProgressEvent<'_>| match event {
+        ProgressEvent::BatchStarted { files, bytes } => {
+            eprintln!(
+                "Checking {files} files ({:.1} MiB)",
+                bytes as f64 / 1048576.0
+            );
+        }
+        ProgressEvent::Failed { url, error } => {
+            eprintln!("  failed {url}: {error}");
+        }
+        ProgressEvent::BatchFinished { files, failed } => {
+            eprintln!("  {} of {files} files ready", files - failed);
+        }
+        _ => {}
+    })
+}
+
+async fn fetch(mcdl: &Mcdl, id: &str) -> Result<(VersionRelease, VersionManifest), Failure> {
+    let root: RootManifest = mcdl.fetch_root_manifest().await?;
+    let release = root.resolve(id)?.clone();
+    let manifest = mcdl.fetch_version_manifest(&release).await?;
+    Ok((release, manifest))
+}
+
+fn info(release: VersionRelease, manifest: &VersionManifest, json: bool) -> Result<(), Failure> {
+    let downloads: Vec<_> = manifest
+        .downloads
+        .iter()
+        .flat_map(|downloads| {
+            [
+                ("client", Some(&downloads.client)),
+                ("client_mappings", downloads.client_mappings.as_ref()),
+                ("server", downloads.server.as_ref()),
+                ("server_mappings", downloads.server_mappings.as_ref()),
+            ]
+            .into_iter()
+            .filter_map(|(name, download)| Some((name, download?)))
+            .chain(
+                downloads
+                    .other
+                    .iter()
+                    .map(|(name, download)| (name.as_str(), download)),
+            )
+        })
+        .collect();
+
+    if json {
+        return print_json(&json!({
+            "id": release.id,
+            "type": release.kind,
+            "releaseTime": manifest.release_time,
+            "complianceLevel": release.compliance_level,
+            "mainClass": manifest.main_class,
+            "javaVersion": manifest.java_version,
+            "assets": manifest.assets,
+            "libraries": manifest.libraries.len(),
+            "downloads": downloads.iter().map(|(name, download)| (*name, *download)).collect::<std::collections::BTreeMap<_, _>>(),
+        }));
+    }
+
+    println!("{} ({})", release.id, release.kind.as_str());
+    println!("  released    {}", manifest.release_time);
+    if let Some(java) = &manifest.java_version {
+        println!("  java        {} ({})", java.major_version, java.component);
+    }
+    println!("  main class  {}", manifest.main_class);
+    if let Some(assets) = &manifest.assets {
+        println!("  assets      {assets}");
+    }
+    println!("  libraries   {}", manifest.libraries.len());
+    println!("  downloads");
+    for (name, download) in downloads {
+        println!(
+            "    {name:<16} {:>9.1} MiB  {}",
+            download.size as f64 / 1048576.0,
+            download.sha1
+        );
+    }
+    Ok(())
+}
+
+fn print_json(value: &impl serde::Serialize) -> Result<(), Failure> {
+    let mut stdout = std::io::stdout().lock();
+    serde_json::to_writer_pretty(&mut stdout, value).map_err(std::io::Error::from)?;
+    writeln!(stdout)?;
+    Ok(())
+}
+
+impl From<Kind> for ReleaseKind {
+    fn from(kind: Kind) -> Self {
+        match kind {
+            Kind::Release => Self::Release,
+            Kind::Snapshot => Self::Snapshot,
+            Kind::OldBeta => Self::OldBeta,
+            Kind::OldAlpha => Self::OldAlpha,
+        }
+    }
+}
+
+impl File {
+    fn select(self, manifest: &VersionManifest) -> Option<&DownloadInfo> {
+        let downloads = manifest.downloads.as_ref()?;
+        match self {
+            Self::Client => Some(&downloads.client),
+            Self::Server => downloads.server.as_ref(),
+            Self::Mappings | Self::ClientMappings => downloads.client_mappings.as_ref(),
+            Self::ServerMappings => downloads.server_mappings.as_ref(),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Client => "client",
+            Self::Server => "server",
+            Self::Mappings | Self::ClientMappings => "client-mappings",
+            Self::ServerMappings => "server-mappings",
+        }
+    }
+
+    fn is_mappings(self) -> bool {
+        !matches!(self, Self::Client | Self::Server)
+    }
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .or_else(|_| value.parse::<DateTime<Utc>>().map(|time| time.date_naive()))
+        .map_err(|_| format!("`{value}` is not a date like 2024-06-13"))
+}
+
+fn parse_range(value: &str) -> Result<(String, String), String> {
+    match value.split_once("..") {
+        Some((from, to)) if !from.is_empty() && !to.is_empty() => {
+            Ok((from.to_owned(), to.to_owned()))
+        }
+        _ => Err(format!("`{value}` is not a range like 1.16..1.20.4")),
+    }
+}
+
+fn parse_mirror(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((from, to)) if !from.is_empty() && !to.is_empty() => {
+            Ok((from.to_owned(), to.to_owned()))
+        }
+        _ => Err(format!("`{value}` is not a rule like HOST=BASE")),
+    }
+}
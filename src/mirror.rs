@@ -0,0 +1,118 @@
+use crate::Result;
+use url::Url;
+
+/// Rules for fetching Mojang's files from somewhere else.
+///
+/// A rule maps either a whole host or a URL prefix to an ordered list of
+/// replacements, which are tried in turn until one of them works. Unless
+/// disabled with [`origin_fallback`](Self::origin_fallback), the original URL
+/// is tried last. Prefix rules take precedence over host rules, and longer
+/// prefixes over shorter ones.
+///
+/// Files listed with a sha1 are checked against it, so a mirror serving
+/// different bytes fails over to the next candidate instead of being trusted.
+/// The root manifest and the Java runtime index have no published sha1 though,
+/// and every other sha1 comes from them, so they are fetched from the origin
+/// unless mirroring them is enabled with [`indexes`](Self::indexes).
+#[derive(Debug, Clone)]
+pub struct Mirrors {
+    hosts: Vec<(String, Vec<String>)>,
+    prefixes: Vec<(String, Vec<String>)>,
+    origin_fallback: bool,
+    indexes: bool,
+}
+
+impl Mirrors {
+    pub fn new() -> Self {
+        Self {
+            hosts: Vec::new(),
+            prefixes: Vec::new(),
+            origin_fallback: true,
+            indexes: false,
+        }
+    }
+
+    /// Serve everything on `host` from `base` instead, keeping the path.
+    /// Calling this again for the same host adds another fallback.
+    pub fn host(mut self, host: &str, base: &str) -> Self {
+        let base = base.trim_end_matches('/').to_owned();
+        add(&mut self.hosts, host.to_ascii_lowercase(), base);
+        self
+    }
+
+    /// Replace `prefix` with `replacement` in URLs starting with it.
+    /// Calling this again for the same prefix adds another fallback.
+    pub fn prefix(mut self, prefix: &str, replacement: &str) -> Self {
+        add(
+            &mut self.prefixes,
+            prefix.to_owned(),
+            replacement.to_owned(),
+        );
+        self.prefixes
+            .sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+        self
+    }
+
+    /// Whether to try the original URL after all mirrors failed, `true` by default
+    pub fn origin_fallback(mut self, enabled: bool) -> Self {
+        self.origin_fallback = enabled;
+        self
+    }
+
+    /// Whether to mirror the root manifest and the Java runtime index too,
+    /// `false` by default. Only enable this for a mirror you trust: whoever
+    /// serves these decides the sha1 every other file is checked against.
+    pub fn indexes(mut self, enabled: bool) -> Self {
+        self.indexes = enabled;
+        self
+    }
+
+    /// The URLs to try for `url`, in order. Never empty.
+    pub fn candidates(&self, url: &Url) -> Result<Vec<Url>> {
+        let mut candidates = Vec::new();
+        if let Some((prefix, replacements)) = self
+            .prefixes
+            .iter()
+            .find(|(prefix, _)| url.as_str().starts_with(prefix.as_str()))
+        {
+            let rest = &url.as_str()[prefix.len()..];
+            for replacement in replacements {
+                candidates.push(Url::parse(&format!("{replacement}{rest}"))?);
+            }
+        } else if let Some((_, bases)) = url
+            .host_str()
+            .and_then(|host| self.hosts.iter().find(|(name, _)| name == host))
+        {
+            let rest = &url[url::Position::BeforePath..];
+            for base in bases {
+                candidates.push(Url::parse(&format!("{base}{rest}"))?);
+            }
+        }
+        if self.origin_fallback || candidates.is_empty() {
+            candidates.push(url.clone());
+        }
+        Ok(candidates)
+    }
+
+    /// Like [`candidates`](Self::candidates), for an index without a sha1
+    pub(crate) fn index_candidates(&self, url: &Url) -> Result<Vec<Url>> {
+        if self.indexes {
+            self.candidates(url)
+        } else {
+            Ok(vec![url.clone()])
+        }
+    }
+}
+
+impl Default for Mirrors {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn add(rules: &mut Vec<(String, Vec<String>)>, key: String, target: String) {
+    match rules.iter_mut().find(|(existing, _)| *existing == key) {
+        Some((_, targets)) => targets.push(target),
+        None => rules.push((key, vec![target])),
+    }
+}
@@ -0,0 +1,65 @@
+use crate::{Library, LibraryExtractInstructions, Mcdl, Platform, Result};
+use bytes::Bytes;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+impl Mcdl {
+    /// Download a library's native classifier for `platform` and unpack it into
+    /// `natives_dir`, leaving out the prefixes listed in its `extract.exclude`
+    /// (usually `META-INF/`).
+    ///
+    /// The library's rules are not consulted, so check
+    /// [`Library::is_allowed_on`] first. Returns `false` if the library has no
+    /// natives for `platform`.
+    pub async fn extract_natives(
+        &self,
+        library: &Library,
+        platform: &Platform,
+        natives_dir: &Path,
+    ) -> Result<bool> {
+        let Some(native) = library.native_for(platform) else {
+            return Ok(false);
+        };
+        let jar = self.download(native).await?;
+        extract(jar, natives_dir, &library.extract).await?;
+        Ok(true)
+    }
+}
+
+/// Unpack a natives jar into `dest`, skipping entries under excluded prefixes
+pub(crate) async fn extract(
+    jar: Bytes,
+    dest: &Path,
+    instructions: &LibraryExtractInstructions,
+) -> Result<()> {
+    let dest = dest.to_owned();
+    let exclude = instructions.exclude.clone();
+    tokio::task::spawn_blocking(move || extract_blocking(jar, &dest, &exclude))
+        .await
+        .map_err(std::io::Error::other)?
+}
+
+fn extract_blocking(jar: Bytes, dest: &Path, exclude: &[String]) -> Result<()> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(jar)).map_err(std::io::Error::from)?;
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(std::io::Error::from)?;
+        // Entries that would escape `dest` have no enclosed name and are skipped
+        let Some(relative) = entry.enclosed_name() else {
+            continue;
+        };
+        let name = entry.name().map_err(std::io::Error::from)?;
+        let excluded = exclude
+            .iter()
+            .any(|prefix| name.starts_with(prefix.as_str()));
+        if entry.is_dir() || excluded {
+            continue;
+        }
+        let path: PathBuf = dest.join(relative);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::File::create(&path)?;
+        std::io::copy(&mut entry, &mut file)?;
+    }
+    Ok(())
+}
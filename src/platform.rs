@@ -0,0 +1,254 @@
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+
+/// The machine a version is being prepared for, which need not be this one
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Platform {
+    /// The OS family, such as [`OsName::Osx`] rather than [`OsName::OsxArm64`]
+    pub os: OsName,
+    pub arch: Arch,
+    /// Matched against `os.version` rules, which only appear on old versions
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub os_version: Option<String>,
+}
+
+/// An OS key as used by library `natives` maps and `os.name` rules.
+///
+/// Newer manifests qualify some keys with an architecture. Keys this crate
+/// doesn't know are kept verbatim in [`OsName::Unknown`].
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[serde(from = "String", into = "String")]
+pub enum OsName {
+    Linux,
+    LinuxArm32,
+    LinuxArm64,
+    Windows,
+    WindowsArm64,
+    Osx,
+    OsxArm64,
+    Unknown(String),
+}
+
+/// A CPU architecture as used by `os.arch` rules
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[serde(from = "String", into = "String")]
+pub enum Arch {
+    X86,
+    X86_64,
+    Arm,
+    Aarch64,
+    Unknown(String),
+}
+
+impl Platform {
+    pub fn new(os: OsName, arch: Arch) -> Self {
+        Self {
+            os,
+            arch,
+            os_version: None,
+        }
+    }
+
+    /// The host, without an OS version since there is no portable way to get one
+    pub fn current() -> Self {
+        Self::new(OsName::current(), Arch::current())
+    }
+
+    pub fn with_os_version(mut self, os_version: impl Into<String>) -> Self {
+        self.os_version = Some(os_version.into());
+        self
+    }
+
+    /// The arch-qualified OS key for this platform, if there is one
+    pub fn qualified_os(&self) -> Option<OsName> {
+        match (self.os.family(), &self.arch) {
+            (OsName::Linux, Arch::Arm) => Some(OsName::LinuxArm32),
+            (OsName::Linux, Arch::Aarch64) => Some(OsName::LinuxArm64),
+            (OsName::Windows, Arch::Aarch64) => Some(OsName::WindowsArm64),
+            (OsName::Osx, Arch::Aarch64) => Some(OsName::OsxArm64),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Platform {
+    fn default() -> Self {
+        Self::current()
+    }
+}
+
+impl OsName {
+    /// The host OS family, or [`OsName::Unknown`] with Rust's name for it
+    pub fn current() -> Self {
+        if cfg!(target_os = "windows") {
+            Self::Windows
+        } else if cfg!(target_os = "macos") {
+            Self::Osx
+        } else if cfg!(target_os = "linux") {
+            Self::Linux
+        } else {
+            Self::Unknown(std::env::consts::OS.to_owned())
+        }
+    }
+
+    pub fn is_current(&self) -> bool {
+        self.matches(&Platform::current())
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Linux => "linux",
+            Self::LinuxArm32 => "linux-arm32",
+            Self::LinuxArm64 => "linux-arm64",
+            Self::Windows => "windows",
+            Self::WindowsArm64 => "windows-arm64",
+            Self::Osx => "osx",
+            Self::OsxArm64 => "osx-arm64",
+            Self::Unknown(name) => name,
+        }
+    }
+
+    /// The key without its architecture qualifier
+    pub fn family(&self) -> Self {
+        match self {
+            Self::Linux | Self::LinuxArm32 | Self::LinuxArm64 => Self::Linux,
+            Self::Windows | Self::WindowsArm64 => Self::Windows,
+            Self::Osx | Self::OsxArm64 => Self::Osx,
+            Self::Unknown(name) => Self::Unknown(name.clone()),
+        }
+    }
+
+    /// The architecture this key is qualified with, if any
+    pub fn arch(&self) -> Option<Arch> {
+        match self {
+            Self::LinuxArm32 => Some(Arch::Arm),
+            Self::LinuxArm64 | Self::WindowsArm64 | Self::OsxArm64 => Some(Arch::Aarch64),
+            _ => None,
+        }
+    }
+
+    /// Whether this key names `platform`, taking a qualifier into account
+    pub fn matches(&self, platform: &Platform) -> bool {
+        self.family() == platform.os.family()
+            && self.arch().is_none_or(|arch| arch == platform.arch)
+    }
+}
+
+impl Arch {
+    /// The host architecture, or [`Arch::Unknown`] with Rust's name for it
+    pub fn current() -> Self {
+        if cfg!(target_arch = "x86") {
+            Self::X86
+        } else if cfg!(target_arch = "x86_64") {
+            Self::X86_64
+        } else if cfg!(target_arch = "arm") {
+            Self::Arm
+        } else if cfg!(target_arch = "aarch64") {
+            Self::Aarch64
+        } else {
+            Self::Unknown(std::env::consts::ARCH.to_owned())
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::X86 => "x86",
+            Self::X86_64 => "x86_64",
+            Self::Arm => "arm",
+            Self::Aarch64 => "aarch64",
+            Self::Unknown(name) => name,
+        }
+    }
+
+    /// Value of the `${arch}` placeholder in old natives classifiers such as
+    /// `natives-windows-${arch}`
+    pub fn bits(&self) -> &'static str {
+        match self {
+            Self::X86 | Self::Arm => "32",
+            Self::X86_64 | Self::Aarch64 => "64",
+            Self::Unknown(_) if cfg!(target_pointer_width = "32") => "32",
+            Self::Unknown(_) => "64",
+        }
+    }
+}
+
+impl FromStr for OsName {
+    type Err = Infallible;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(match name {
+            "linux" => Self::Linux,
+            "linux-arm32" => Self::LinuxArm32,
+            "linux-arm64" => Self::LinuxArm64,
+            "windows" => Self::Windows,
+            "windows-arm64" => Self::WindowsArm64,
+            "osx" => Self::Osx,
+            "osx-arm64" => Self::OsxArm64,
+            other => Self::Unknown(other.to_owned()),
+        })
+    }
+}
+
+impl FromStr for Arch {
+    type Err = Infallible;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(match name {
+            "x86" => Self::X86,
+            "x86_64" => Self::X86_64,
+            "arm" => Self::Arm,
+            "aarch64" => Self::Aarch64,
+            other => Self::Unknown(other.to_owned()),
+        })
+    }
+}
+
+impl From<String> for OsName {
+    fn from(name: String) -> Self {
+        match name.parse() {
+            Ok(Self::Unknown(_)) => Self::Unknown(name),
+            Ok(known) => known,
+        }
+    }
+}
+
+impl From<OsName> for String {
+    fn from(name: OsName) -> Self {
+        match name {
+            OsName::Unknown(name) => name,
+            known => known.as_str().to_owned(),
+        }
+    }
+}
+
+impl From<String> for Arch {
+    fn from(name: String) -> Self {
+        match name.parse() {
+            Ok(Self::Unknown(_)) => Self::Unknown(name),
+            Ok(known) => known,
+        }
+    }
+}
+
+impl From<Arch> for String {
+    fn from(arch: Arch) -> Self {
+        match arch {
+            Arch::Unknown(name) => name,
+            known => known.as_str().to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for OsName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Display for Arch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
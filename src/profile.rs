@@ -0,0 +1,101 @@
+use md5::{Digest, Md5};
+use uuid::Uuid;
+
+/// Access token passed for offline profiles. The game only checks it online.
+const OFFLINE_ACCESS_TOKEN: &str = "0";
+
+/// The player a game is launched as
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    /// Undashed UUID, as the launcher passes it
+    pub uuid: String,
+    pub access_token: String,
+    /// `msa` for Microsoft accounts, `legacy` for offline ones
+    pub user_type: String,
+    pub xuid: Option<String>,
+    pub client_id: Option<String>,
+}
+
+impl Profile {
+    pub fn new(
+        name: impl Into<String>,
+        uuid: impl Into<String>,
+        access_token: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            uuid: uuid.into(),
+            access_token: access_token.into(),
+            user_type: "msa".to_owned(),
+            xuid: None,
+            client_id: None,
+        }
+    }
+
+    /// A profile for offline play, with the same UUID a vanilla server in
+    /// offline mode assigns to `name`
+    pub fn offline(name: impl Into<String>) -> Self {
+        let name = name.into();
+        let uuid = offline_uuid(&name).simple().to_string();
+        Self {
+            user_type: "legacy".to_owned(),
+            ..Self::new(name, uuid, OFFLINE_ACCESS_TOKEN)
+        }
+    }
+
+    /// Values for the `auth_*`, `user_type` and related launch placeholders
+    pub fn placeholders(&self) -> [(&'static str, String); 7] {
+        [
+            ("auth_player_name", self.name.clone()),
+            ("auth_uuid", self.uuid.clone()),
+            ("auth_access_token", self.access_token.clone()),
+            (
+                "auth_session",
+                format!("token:{}:{}", self.access_token, self.uuid),
+            ),
+            ("user_type", self.user_type.clone()),
+            ("auth_xuid", self.xuid.clone().unwrap_or_default()),
+            ("clientid", self.client_id.clone().unwrap_or_default()),
+        ]
+    }
+}
+
+/// Java's `UUID.nameUUIDFromBytes("OfflinePlayer:<name>")`: an MD5, name-based
+/// version 3 UUID without a namespace
+pub fn offline_uuid(name: &str) -> Uuid {
+    let digest = Md5::digest(format!("OfflinePlayer:{name}"));
+    uuid::Builder::from_md5_bytes(digest.into()).into_uuid()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_the_uuid_of_offline_mode_servers() {
+        assert_eq!(
+            offline_uuid("Notch").to_string(),
+            "b50ad385-829d-3141-a216-7e7d7539ba7f"
+        );
+    }
+
+    #[test]
+    fn fills_offline_placeholders() {
+        let placeholders = Profile::offline("Notch").placeholders();
+        let value = |key| {
+            let (_, value) = placeholders.iter().find(|(name, _)| *name == key).unwrap();
+            value.as_str()
+        };
+        assert_eq!(value("auth_player_name"), "Notch");
+        assert_eq!(value("auth_uuid"), "b50ad385829d3141a2167e7d7539ba7f");
+        assert_eq!(value("auth_access_token"), "0");
+        assert_eq!(
+            value("auth_session"),
+            "token:0:b50ad385829d3141a2167e7d7539ba7f"
+        );
+        assert_eq!(value("user_type"), "legacy");
+        assert_eq!(value("auth_xuid"), "");
+        assert_eq!(value("clientid"), "");
+    }
+}
@@ -0,0 +1,141 @@
+use crate::transport::ByteStream;
+use crate::{Error, Result};
+use futures_util::{StreamExt, stream};
+use std::fmt;
+use std::sync::Arc;
+use url::Url;
+
+/// Something that happened while downloading, as seen by a [`Progress`]
+#[derive(Debug, Clone, Copy)]
+pub enum ProgressEvent<'a> {
+    /// [`Mcdl::download_all`](crate::Mcdl::download_all) is about to fetch
+    /// `files` files totalling `bytes` bytes
+    BatchStarted {
+        files: usize,
+        bytes: u64,
+    },
+    /// Every file of a batch was attempted, `failed` of them unsuccessfully
+    BatchFinished {
+        files: usize,
+        failed: usize,
+    },
+    /// A file of `size` bytes, as published in its manifest, is being fetched
+    Started {
+        url: &'a Url,
+        size: u64,
+    },
+    /// `downloaded` of a file's `size` bytes have arrived so far. Resumed
+    /// downloads start out at the size of what was already on disk.
+    Progress {
+        url: &'a Url,
+        downloaded: u64,
+        size: u64,
+    },
+    /// A file is complete and verified, or was already present
+    Finished {
+        url: &'a Url,
+        size: u64,
+    },
+    Failed {
+        url: &'a Url,
+        error: &'a Error,
+    },
+}
+
+/// Receives [`ProgressEvent`]s for every download made through an
+/// [`Mcdl`](crate::Mcdl) it is attached to.
+///
+/// Events can arrive concurrently from several downloads of a batch, so an
+/// observer should be quick and not block. Closures taking an event implement
+/// this, which makes forwarding events into a channel a one-liner.
+pub trait Progress: Send + Sync {
+    fn event(&self, event: ProgressEvent<'_>);
+}
+
+impl<F> Progress for F
+where
+    F: Fn(ProgressEvent<'_>) + Send + Sync,
+{
+    fn event(&self, event: ProgressEvent<'_>) {
+        self(event)
+    }
+}
+
+/// The optional observer of an [`Mcdl`](crate::Mcdl)
+#[derive(Clone, Default)]
+pub(crate) struct Observer(Option<Arc<dyn Progress>>);
+
+impl Observer {
+    pub(crate) fn new(progress: impl Progress + 'static) -> Self {
+        Self(Some(Arc::new(progress)))
+    }
+
+    pub(crate) fn emit(&self, event: ProgressEvent<'_>) {
+        if let Some(progress) = &self.0 {
+            progress.event(event);
+        }
+    }
+
+    /// Report the start and outcome of fetching one file
+    pub(crate) async fn observe<T>(
+        &self,
+        url: &Url,
+        size: u64,
+        fetch: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        self.emit(ProgressEvent::Started { url, size });
+        let result = fetch.await;
+        match &result {
+            Ok(_) => self.emit(ProgressEvent::Finished { url, size }),
+            Err(error) => self.emit(ProgressEvent::Failed { url, error }),
+        }
+        result
+    }
+
+    /// Report every chunk of a stream as it is consumed, and its outcome. The
+    /// caller reports the start, before connecting.
+    pub(crate) fn observe_stream(&self, inner: ByteStream, url: Url, size: u64) -> ByteStream {
+        if self.0.is_none() {
+            return inner;
+        }
+        let state = (inner, self.clone(), url, 0, false);
+        Box::pin(stream::unfold(
+            state,
+            move |(mut inner, observer, url, mut downloaded, done)| async move {
+                if done {
+                    return None;
+                }
+                match inner.next().await {
+                    Some(Ok(chunk)) => {
+                        downloaded += chunk.len() as u64;
+                        observer.emit(ProgressEvent::Progress {
+                            url: &url,
+                            downloaded,
+                            size,
+                        });
+                        Some((Ok(chunk), (inner, observer, url, downloaded, false)))
+                    }
+                    Some(Err(error)) => {
+                        observer.emit(ProgressEvent::Failed {
+                            url: &url,
+                            error: &error,
+                        });
+                        Some((Err(error), (inner, observer, url, downloaded, true)))
+                    }
+                    None => {
+                        observer.emit(ProgressEvent::Finished { url: &url, size });
+                        None
+                    }
+                }
+            },
+        ))
+    }
+}
+
+impl fmt::Debug for Observer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Observer")
+            .field(&self.0.as_ref().map(|_| "Progress"))
+            .finish()
+    }
+}
@@ -0,0 +1,241 @@
+use crate::{Error, ReleaseKind, Result, RootManifest, VersionRelease};
+use chrono::{DateTime, Utc};
+
+/// Selector for the newest release, accepted by [`RootManifest::resolve`]
+pub const LATEST: &str = "latest";
+/// Selector for the newest snapshot, accepted by [`RootManifest::resolve`]
+pub const LATEST_SNAPSHOT: &str = "latest-snapshot";
+
+impl RootManifest {
+    pub fn latest_release(&self) -> Result<&VersionRelease> {
+        self.version(&self.latest.release)
+    }
+
+    pub fn latest_snapshot(&self) -> Result<&VersionRelease> {
+        self.version(&self.latest.snapshot)
+    }
+
+    /// Look up `latest`, `latest-snapshot` or an exact version id
+    pub fn resolve(&self, selector: &str) -> Result<&VersionRelease> {
+        match selector {
+            LATEST => self.latest_release(),
+            LATEST_SNAPSHOT => self.latest_snapshot(),
+            id => self.version(id),
+        }
+    }
+
+    /// Start a query over every version, see [`VersionQuery`]
+    pub fn query(&self) -> VersionQuery<'_> {
+        VersionQuery {
+            manifest: self,
+            kinds: Vec::new(),
+            compliance_levels: Vec::new(),
+            since: None,
+            until: None,
+            from: None,
+            to: None,
+        }
+    }
+}
+
+/// Filters over the versions of a [`RootManifest`].
+///
+/// Every filter narrows the result, and repeating [`kind`](Self::kind) or
+/// [`compliance_level`](Self::compliance_level) accepts any of the given values.
+/// Matches come back oldest first by release time.
+#[derive(Debug, Clone)]
+pub struct VersionQuery<'a> {
+    manifest: &'a RootManifest,
+    kinds: Vec<ReleaseKind>,
+    compliance_levels: Vec<u8>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    from: Option<&'a str>,
+    to: Option<&'a str>,
+}
+
+impl<'a> VersionQuery<'a> {
+    pub fn kind(mut self, kind: ReleaseKind) -> Self {
+        self.kinds.push(kind);
+        self
+    }
+
+    pub fn compliance_level(mut self, level: u8) -> Self {
+        self.compliance_levels.push(level);
+        self
+    }
+
+    /// Versions released at or after `time`
+    pub fn since(mut self, time: DateTime<Utc>) -> Self {
+        self.since = Some(time);
+        self
+    }
+
+    /// Versions released at or before `time`
+    pub fn until(mut self, time: DateTime<Utc>) -> Self {
+        self.until = Some(time);
+        self
+    }
+
+    /// Versions released between two versions, both included. Either end may
+    /// be anything [`RootManifest::resolve`] accepts, such as `latest`.
+    pub fn between(mut self, from: &'a str, to: &'a str) -> Self {
+        self.from = Some(from);
+        self.to = Some(to);
+        self
+    }
+
+    /// Fails with [`Error::UnknownVersion`] if an end of
+    /// [`between`](Self::between) is not in the manifest
+    pub fn run(self) -> Result<Vec<&'a VersionRelease>> {
+        let manifest = self.manifest;
+        let release_time = |selector| Ok::<_, Error>(manifest.resolve(selector)?.release_time);
+        let since = self.from.map(release_time).transpose()?.max(self.since);
+        let until = match (self.to.map(release_time).transpose()?, self.until) {
+            (Some(to), Some(until)) => Some(to.min(until)),
+            (to, until) => to.or(until),
+        };
+
+        let mut versions: Vec<_> = manifest
+            .versions
+            .iter()
+            .filter(|version| self.kinds.is_empty() || self.kinds.contains(&version.kind))
+            .filter(|version| {
+                self.compliance_levels.is_empty()
+                    || self.compliance_levels.contains(&version.compliance_level)
+            })
+            .filter(|version| since.is_none_or(|since| version.release_time >= since))
+            .filter(|version| until.is_none_or(|until| version.release_time <= until))
+            .collect();
+        versions.sort_by_key(|version| version.release_time);
+        Ok(versions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A root manifest listing `(id, type, release date, compliance level)`
+    /// out of chronological order
+    fn manifest() -> RootManifest {
+        let versions: Vec<_> = [
+            ("1.16.5", "release", "2021-01-15", 0),
+            ("21w03a", "snapshot", "2021-01-20", 0),
+            ("1.16.4", "release", "2020-11-02", 0),
+            ("1.17", "release", "2021-06-08", 1),
+            ("20w45a", "snapshot", "2020-11-04", 0),
+            ("b1.7.3", "old_beta", "2011-07-08", 0),
+            ("1.17-pre1", "snapshot", "2021-05-27", 1),
+        ]
+        .iter()
+        .map(|(id, kind, date, level)| {
+            json!({
+                "id": id,
+                "type": kind,
+                "url": format!("https://piston-meta.mojang.com/v1/packages/{id}.json"),
+                "time": format!("{date}T00:00:00+00:00"),
+                "releaseTime": format!("{date}T00:00:00+00:00"),
+                "sha1": "0".repeat(40),
+                "complianceLevel": level,
+            })
+        })
+        .collect();
+        let root = json!({
+            "latest": { "release": "1.17", "snapshot": "1.17-pre1" },
+            "versions": versions,
+        });
+        crate::from_json(root.to_string().as_bytes()).unwrap()
+    }
+
+    fn date(day: &str) -> DateTime<Utc> {
+        format!("{day}T00:00:00Z").parse().unwrap()
+    }
+
+    fn ids(query: VersionQuery<'_>) -> Vec<&str> {
+        let versions = query.run().unwrap();
+        versions.iter().map(|version| version.id.as_str()).collect()
+    }
+
+    #[test]
+    fn lists_versions_oldest_first() {
+        let manifest = manifest();
+        assert_eq!(
+            ids(manifest.query()),
+            [
+                "b1.7.3",
+                "1.16.4",
+                "20w45a",
+                "1.16.5",
+                "21w03a",
+                "1.17-pre1",
+                "1.17"
+            ]
+        );
+    }
+
+    #[test]
+    fn includes_both_date_bounds() {
+        let manifest = manifest();
+        let query = manifest
+            .query()
+            .since(date("2020-11-04"))
+            .until(date("2021-01-15"));
+        assert_eq!(ids(query), ["20w45a", "1.16.5"]);
+    }
+
+    #[test]
+    fn narrows_between_with_dates() {
+        let manifest = manifest();
+        let between = || manifest.query().between("1.16.4", LATEST);
+        assert_eq!(
+            ids(between()),
+            ["1.16.4", "20w45a", "1.16.5", "21w03a", "1.17-pre1", "1.17"]
+        );
+        // The tighter of the two bounds wins at either end
+        assert_eq!(
+            ids(between()
+                .since(date("2021-01-01"))
+                .until(date("2021-01-20"))),
+            ["1.16.5", "21w03a"]
+        );
+        assert_eq!(
+            ids(between()
+                .since(date("2000-01-01"))
+                .until(date("2030-01-01"))),
+            ids(between())
+        );
+    }
+
+    #[test]
+    fn filters_by_kind_and_compliance_level() {
+        let manifest = manifest();
+        let query = manifest.query().kind(ReleaseKind::Release);
+        assert_eq!(ids(query), ["1.16.4", "1.16.5", "1.17"]);
+        let query = manifest
+            .query()
+            .kind(ReleaseKind::Release)
+            .kind(ReleaseKind::OldBeta);
+        assert_eq!(ids(query), ["b1.7.3", "1.16.4", "1.16.5", "1.17"]);
+        let query = manifest.query().compliance_level(1);
+        assert_eq!(ids(query), ["1.17-pre1", "1.17"]);
+        let query = manifest
+            .query()
+            .kind(ReleaseKind::Snapshot)
+            .compliance_level(0);
+        assert_eq!(ids(query), ["20w45a", "21w03a"]);
+    }
+
+    #[test]
+    fn fails_on_an_unknown_end() {
+        let manifest = manifest();
+        for (from, to) in [("1.16.4", "1.99"), ("1.99", LATEST_SNAPSHOT)] {
+            let err = manifest.query().between(from, to).run().unwrap_err();
+            assert!(
+                matches!(&err, Error::UnknownVersion(id) if id == "1.99"),
+                "{err}"
+            );
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use crate::{Error, Result};
+use reqwest::StatusCode;
+use std::io::ErrorKind;
+use std::time::Duration;
+
+/// How often and how patiently failed requests are retried.
+///
+/// Attempt `n` waits `initial_backoff * multiplier^(n - 1)`, capped at
+/// `max_backoff`, with the upper half of that randomised so that many clients
+/// failing together do not hammer the server again in lockstep. Only errors
+/// accepted by the `retry_on` predicate are retried, by default
+/// [`Error::is_transient`].
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    retry_on: fn(&Error) -> bool,
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+            multiplier: 2.0,
+            retry_on: Error::is_transient,
+        }
+    }
+
+    /// Fail on the first error
+    pub fn none() -> Self {
+        Self::new().max_attempts(1)
+    }
+
+    /// Total number of tries, including the first one
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Growth of the backoff per retry. Negative values are treated as 0 and
+    /// NaN as 1, that is a constant backoff
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = if multiplier.is_nan() {
+            1.0
+        } else {
+            multiplier.max(0.0)
+        };
+        self
+    }
+
+    /// Decide which errors are worth another attempt
+    pub fn retry_on(mut self, retry_on: fn(&Error) -> bool) -> Self {
+        self.retry_on = retry_on;
+        self
+    }
+
+    /// Delay before retry number `retry`, counting from 1, without jitter
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let secs = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent);
+        // Also catches infinity and the NaN of a zero backoff times infinity
+        if secs < self.max_backoff.as_secs_f64() {
+            Duration::from_secs_f64(secs)
+        } else {
+            self.max_backoff
+        }
+    }
+
+    /// Run `op` until it succeeds, fails with a permanent error or runs out of
+    /// attempts, returning the last error in the latter cases
+    pub(crate) async fn run<T, F, Fut>(&self, mut op: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match op().await {
+                Err(err) if attempt < self.max_attempts && (self.retry_on)(&err) => {
+                    let backoff = self.backoff(attempt);
+                    let half = backoff / 2;
+                    tokio::time::sleep(half + half.mul_f64(fastrand::f64())).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Error {
+    /// Whether the error may go away by trying again: server errors, rate
+    /// limiting, timeouts and dropped connections
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Status { status, .. } => {
+                status.is_server_error()
+                    || *status == StatusCode::TOO_MANY_REQUESTS
+                    || *status == StatusCode::REQUEST_TIMEOUT
+            }
+            Self::Transport(err) => !err.is_builder() && !err.is_redirect(),
+            Self::Io(err) => matches!(
+                err.kind(),
+                ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::ConnectionRefused
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::TimedOut
+                    | ErrorKind::UnexpectedEof
+                    | ErrorKind::Interrupted
+            ),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn status(status: u16) -> Error {
+        Error::Status {
+            url: "https://example.com/".parse().unwrap(),
+            status: StatusCode::from_u16(status).unwrap(),
+        }
+    }
+
+    #[test]
+    fn backoff_grows_up_to_the_cap() {
+        let policy = RetryPolicy::new();
+        let millis: Vec<_> = (1..=7).map(|n| policy.backoff(n).as_millis()).collect();
+        assert_eq!(millis, [500, 1000, 2000, 4000, 8000, 10000, 10000]);
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn backoff_survives_extreme_settings() {
+        let huge = RetryPolicy::new()
+            .initial_backoff(Duration::MAX)
+            .max_backoff(Duration::MAX)
+            .multiplier(f64::INFINITY);
+        assert_eq!(huge.backoff(1), Duration::MAX);
+        assert_eq!(huge.backoff(100), Duration::MAX);
+
+        let zero = RetryPolicy::new()
+            .initial_backoff(Duration::ZERO)
+            .multiplier(f64::INFINITY);
+        assert_eq!(zero.backoff(2), Duration::from_secs(10));
+
+        let constant = RetryPolicy::new().multiplier(f64::NAN);
+        assert_eq!(constant.backoff(5), Duration::from_millis(500));
+
+        let negative = RetryPolicy::new().multiplier(-2.0);
+        assert_eq!(negative.backoff(1), Duration::from_millis(500));
+        assert_eq!(negative.backoff(2), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn run_retries_transient_errors() {
+        let calls = Cell::new(0);
+        let policy = RetryPolicy::new().initial_backoff(Duration::ZERO);
+        let result: Result<()> = policy
+            .run(|| async {
+                calls.set(calls.get() + 1);
+                Err(status(503))
+            })
+            .await;
+        assert!(matches!(result, Err(Error::Status { status, .. }) if status == 503));
+        assert_eq!(calls.get(), 4);
+
+        calls.set(0);
+        let result = policy
+            .run(|| async {
+                calls.set(calls.get() + 1);
+                if calls.get() < 3 {
+                    Err(status(429))
+                } else {
+                    Ok(calls.get())
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn run_stops_on_permanent_errors() {
+        let calls = Cell::new(0);
+        let policy = RetryPolicy::new().initial_backoff(Duration::ZERO);
+        let result: Result<()> = policy
+            .run(|| async {
+                calls.set(calls.get() + 1);
+                Err(status(404))
+            })
+            .await;
+        assert!(matches!(result, Err(Error::Status { status, .. }) if status == 404));
+        assert_eq!(calls.get(), 1);
+    }
+}
@@ -0,0 +1,217 @@
+use crate::{Argument, ArgumentValue, Arguments, Platform, Rule, RuleAction};
+use std::collections::HashMap;
+
+/// Launcher feature names used by argument rules
+pub mod feature {
+    pub const IS_DEMO_USER: &str = "is_demo_user";
+    pub const HAS_CUSTOM_RESOLUTION: &str = "has_custom_resolution";
+    pub const HAS_QUICK_PLAYS_SUPPORT: &str = "has_quick_plays_support";
+    pub const IS_QUICK_PLAY_SINGLEPLAYER: &str = "is_quick_play_singleplayer";
+    pub const IS_QUICK_PLAY_MULTIPLAYER: &str = "is_quick_play_multiplayer";
+    pub const IS_QUICK_PLAY_REALMS: &str = "is_quick_play_realms";
+}
+
+/// Everything rules are evaluated against: the target platform and which
+/// launcher features are enabled. Features that were never set count as off.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RuleContext {
+    pub platform: Platform,
+    pub features: HashMap<String, bool>,
+}
+
+impl RuleContext {
+    pub fn new(platform: Platform) -> Self {
+        Self {
+            platform,
+            features: HashMap::new(),
+        }
+    }
+
+    pub fn with_feature(mut self, name: impl Into<String>, enabled: bool) -> Self {
+        self.features.insert(name.into(), enabled);
+        self
+    }
+
+    pub fn feature(&self, name: &str) -> bool {
+        self.features.get(name).copied().unwrap_or(false)
+    }
+
+    /// Evaluate a rule list the way the launcher does.
+    ///
+    /// An empty list allows. Otherwise everything starts out disallowed and the
+    /// last rule that applies decides.
+    pub fn allows(&self, rules: &[Rule]) -> bool {
+        if rules.is_empty() {
+            return true;
+        }
+        rules
+            .iter()
+            .rev()
+            .find(|rule| rule.applies(self))
+            .is_some_and(|rule| rule.action == RuleAction::Allow)
+    }
+
+    /// The values of `arguments`, dropping those whose rules don't allow them
+    pub fn resolve<'a>(&self, arguments: &'a [Argument]) -> Vec<&'a str> {
+        arguments
+            .iter()
+            .flat_map(|argument| argument.values(self))
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+impl Rule {
+    /// Whether the rule's conditions hold, regardless of its action
+    pub fn applies(&self, context: &RuleContext) -> bool {
+        self.os
+            .as_ref()
+            .is_none_or(|os| os.allows(&context.platform))
+            && self
+                .features
+                .iter()
+                .all(|(name, &expected)| context.feature(name) == expected)
+    }
+}
+
+impl Argument {
+    pub fn values(&self, context: &RuleContext) -> &[String] {
+        match self {
+            Self::Plain(value) => std::slice::from_ref(value),
+            Self::Conditional { rules, value } if context.allows(rules) => match value {
+                ArgumentValue::Single(value) => std::slice::from_ref(value),
+                ArgumentValue::Many(values) => values,
+            },
+            Self::Conditional { .. } => &[],
+        }
+    }
+}
+
+impl Arguments {
+    pub fn resolve_game(&self, context: &RuleContext) -> Vec<&str> {
+        context.resolve(&self.game)
+    }
+
+    pub fn resolve_jvm(&self, context: &RuleContext) -> Vec<&str> {
+        context.resolve(&self.jvm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Arch, OsName};
+    use serde_json::json;
+
+    fn rules(rules: serde_json::Value) -> Vec<Rule> {
+        serde_json::from_value(rules).unwrap()
+    }
+
+    fn context(os: OsName, arch: Arch) -> RuleContext {
+        RuleContext::new(Platform::new(os, arch))
+    }
+
+    #[test]
+    fn empty_rules_allow() {
+        assert!(context(OsName::Linux, Arch::X86_64).allows(&[]));
+    }
+
+    #[test]
+    fn last_applying_rule_wins() {
+        // How LWJGL 2 natives were excluded on macOS
+        let all_but_osx = rules(json!([
+            { "action": "allow" },
+            { "action": "disallow", "os": { "name": "osx" } },
+        ]));
+        assert!(context(OsName::Linux, Arch::X86_64).allows(&all_but_osx));
+        assert!(!context(OsName::Osx, Arch::X86_64).allows(&all_but_osx));
+
+        let only_osx = rules(json!([
+            { "action": "disallow" },
+            { "action": "allow", "os": { "name": "osx" } },
+        ]));
+        assert!(!context(OsName::Linux, Arch::X86_64).allows(&only_osx));
+        assert!(context(OsName::Osx, Arch::Aarch64).allows(&only_osx));
+
+        // Nothing applies, so the list disallows
+        let only_windows = rules(json!([{ "action": "allow", "os": { "name": "windows" } }]));
+        assert!(!context(OsName::Linux, Arch::X86_64).allows(&only_windows));
+    }
+
+    #[test]
+    fn matches_arch_and_os_version() {
+        let x86 = rules(json!([{ "action": "allow", "os": { "arch": "x86" } }]));
+        assert!(context(OsName::Windows, Arch::X86).allows(&x86));
+        assert!(!context(OsName::Windows, Arch::X86_64).allows(&x86));
+
+        let windows_10 = rules(json!([{
+            "action": "allow",
+            "os": { "name": "windows", "version": "^10\\." },
+        }]));
+        let windows = |version| {
+            RuleContext::new(Platform::new(OsName::Windows, Arch::X86_64).with_os_version(version))
+        };
+        assert!(windows("10.0").allows(&windows_10));
+        assert!(!windows("6.1").allows(&windows_10));
+        assert!(!context(OsName::Windows, Arch::X86_64).allows(&windows_10));
+
+        let invalid = rules(json!([{ "action": "allow", "os": { "version": "(" } }]));
+        assert!(!windows("10.0").allows(&invalid));
+    }
+
+    #[test]
+    fn matches_features() {
+        let demo = rules(json!([{ "action": "allow", "features": { "is_demo_user": true } }]));
+        let linux = context(OsName::Linux, Arch::X86_64);
+        assert!(!linux.allows(&demo));
+        assert!(
+            linux
+                .clone()
+                .with_feature(feature::IS_DEMO_USER, true)
+                .allows(&demo)
+        );
+        assert!(
+            !linux
+                .clone()
+                .with_feature(feature::IS_DEMO_USER, false)
+                .allows(&demo)
+        );
+
+        // Every listed feature has to match
+        let quick_play = rules(json!([{
+            "action": "allow",
+            "features": { "has_quick_plays_support": true, "is_quick_play_realms": false },
+        }]));
+        let supported = linux.with_feature(feature::HAS_QUICK_PLAYS_SUPPORT, true);
+        assert!(supported.allows(&quick_play));
+        assert!(
+            !supported
+                .with_feature(feature::IS_QUICK_PLAY_REALMS, true)
+                .allows(&quick_play)
+        );
+    }
+
+    #[test]
+    fn drops_disallowed_arguments() {
+        let arguments: Vec<Argument> = serde_json::from_value(json!([
+            "--username",
+            "${auth_player_name}",
+            { "rules": [{ "action": "allow", "features": { "is_demo_user": true } }], "value": "--demo" },
+            {
+                "rules": [{ "action": "allow", "os": { "name": "linux" } }],
+                "value": ["--linux", "yes"],
+            },
+        ]))
+        .unwrap();
+        let linux = context(OsName::Linux, Arch::X86_64);
+        assert_eq!(
+            linux.resolve(&arguments),
+            ["--username", "${auth_player_name}", "--linux", "yes"]
+        );
+        let demo = context(OsName::Osx, Arch::X86_64).with_feature(feature::IS_DEMO_USER, true);
+        assert_eq!(
+            demo.resolve(&arguments),
+            ["--username", "${auth_player_name}", "--demo"]
+        );
+    }
+}
@@ -0,0 +1,300 @@
+use crate::{Arch, DownloadInfo, Error, Mcdl, OsName, Platform, Result, from_json, fs};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use url::Url;
+
+const JAVA_RUNTIME_INDEX_URL: &str = "https://launchermeta.mojang.com/v1/products/java-runtime/2ec0cc96c44e5a76b9c8b7c39df7210883d12871/all.json";
+
+/// Mojang's index of Java runtimes, keyed by runtime platform (see
+/// [`Platform::java_runtime_key`]) and then by component such as
+/// `java-runtime-gamma`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct JavaRuntimeIndex {
+    pub platforms: HashMap<String, HashMap<String, Vec<JavaRuntime>>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct JavaRuntime {
+    pub availability: RuntimeAvailability,
+    pub manifest: DownloadInfo,
+    pub version: RuntimeVersion,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct RuntimeAvailability {
+    pub group: u32,
+    /// Rollout percentage
+    pub progress: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RuntimeVersion {
+    pub name: String,
+    pub released: DateTime<Utc>,
+}
+
+/// The file tree of one runtime, keyed by path relative to the runtime root
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct JavaRuntimeManifest {
+    pub files: HashMap<String, RuntimeFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RuntimeFile {
+    File {
+        downloads: Box<RuntimeFileDownloads>,
+        #[serde(default)]
+        executable: bool,
+    },
+    Directory,
+    /// A symlink, with a target relative to the link's directory
+    Link {
+        target: String,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RuntimeFileDownloads {
+    pub raw: DownloadInfo,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub lzma: Option<DownloadInfo>,
+}
+
+impl JavaRuntimeIndex {
+    pub async fn fetch() -> Result<Self> {
+        Mcdl::shared().fetch_java_runtime_index().await
+    }
+
+    /// The runtime to install for `component` on `platform`, if Mojang ships one
+    pub fn runtime(&self, component: &str, platform: &Platform) -> Option<&JavaRuntime> {
+        self.platforms
+            .get(platform.java_runtime_key()?)?
+            .get(component)?
+            .first()
+    }
+}
+
+impl Platform {
+    /// Key of this platform in the [`JavaRuntimeIndex`]
+    pub fn java_runtime_key(&self) -> Option<&'static str> {
+        match (self.os.family(), &self.arch) {
+            (OsName::Linux, Arch::X86_64) => Some("linux"),
+            (OsName::Linux, Arch::X86) => Some("linux-i386"),
+            (OsName::Osx, Arch::X86_64) => Some("mac-os"),
+            (OsName::Osx, Arch::Aarch64) => Some("mac-os-arm64"),
+            (OsName::Windows, Arch::X86_64) => Some("windows-x64"),
+            (OsName::Windows, Arch::X86) => Some("windows-x86"),
+            (OsName::Windows, Arch::Aarch64) => Some("windows-arm64"),
+            _ => None,
+        }
+    }
+}
+
+impl Mcdl {
+    pub async fn fetch_java_runtime_index(&self) -> Result<JavaRuntimeIndex> {
+        let url = Url::parse(JAVA_RUNTIME_INDEX_URL)?;
+        from_json(&self.get_index_bytes(&url).await?)
+    }
+
+    pub async fn fetch_java_runtime_manifest(
+        &self,
+        runtime: &JavaRuntime,
+    ) -> Result<JavaRuntimeManifest> {
+        from_json(&self.download(&runtime.manifest).await?)
+    }
+
+    /// Materialise the runtime `component` for `platform` in `dest`.
+    ///
+    /// Executable bits and symlinks are only applied on Unix hosts. Files that
+    /// are already present and intact are left alone.
+    ///
+    /// Fails with [`Error::UnsafePath`] before writing anything if a path or
+    /// link target from the manifest would land outside `dest`, or runs
+    /// through a link, whether declared in the manifest or already on disk,
+    /// since whatever is written through one could end up anywhere.
+    pub async fn install_java_runtime(
+        &self,
+        component: &str,
+        platform: &Platform,
+        dest: &Path,
+    ) -> Result<JavaRuntimeManifest> {
+        let index = self.fetch_java_runtime_index().await?;
+        let runtime = index
+            .runtime(component, platform)
+            .ok_or_else(|| Error::UnknownRuntime {
+                component: component.to_owned(),
+                platform: platform
+                    .java_runtime_key()
+                    .unwrap_or(platform.os.as_str())
+                    .to_owned(),
+            })?;
+        let manifest = self.fetch_java_runtime_manifest(runtime).await?;
+
+        let links: HashSet<&Path> = manifest
+            .files
+            .iter()
+            .filter(|(_, file)| matches!(file, RuntimeFile::Link { .. }))
+            .map(|(name, _)| Path::new(name.as_str()))
+            .collect();
+        let mut files = Vec::with_capacity(manifest.files.len());
+        for (name, file) in &manifest.files {
+            let path = fs::enclosed(dest, name)?;
+            let through_link = Path::new(name)
+                .ancestors()
+                .skip(1)
+                .any(|parent| links.contains(parent));
+            if through_link || fs::has_symlinked_parent(dest, name).await? {
+                return Err(Error::UnsafePath(name.clone()));
+            }
+            if let RuntimeFile::Link { target } = file {
+                // The link itself may point at another link, but not past one
+                let steps = fs::link_steps(name, target)
+                    .ok_or_else(|| Error::UnsafePath(target.clone()))?;
+                if steps.split_last().is_some_and(|(_, passed)| {
+                    passed.iter().any(|step| links.contains(step.as_path()))
+                }) {
+                    return Err(Error::UnsafePath(target.clone()));
+                }
+            }
+            files.push((path, file));
+        }
+
+        for (path, file) in files {
+            match file {
+                RuntimeFile::Directory => tokio::fs::create_dir_all(&path).await?,
+                RuntimeFile::File {
+                    downloads,
+                    executable,
+                } => {
+                    self.download_to(&downloads.raw, &path).await?;
+                    if *executable {
+                        fs::set_executable(&path).await?;
+                    }
+                }
+                RuntimeFile::Link { target } => fs::symlink(target, &path).await?,
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MemoryTransport, RetryPolicy};
+    use serde_json::{Value, json};
+    use sha1::{Digest, Sha1};
+    use std::path::PathBuf;
+
+    const MANIFEST_URL: &str = "https://piston-meta.mojang.com/v1/objects/runtime.json";
+    const FILE_URL: &str = "https://piston-data.mojang.com/v1/objects/evil";
+
+    fn info(url: &str, body: &[u8]) -> Value {
+        json!({
+            "sha1": format!("{:x}", Sha1::digest(body)),
+            "size": body.len(),
+            "url": url,
+        })
+    }
+
+    /// A runtime index and manifest serving `files`, each file holding `evil`
+    fn mcdl(files: Value) -> Mcdl {
+        let manifest = json!({ "files": files }).to_string();
+        let index = json!({
+            "linux": {
+                "java-runtime-gamma": [{
+                    "availability": { "group": 1, "progress": 100 },
+                    "manifest": info(MANIFEST_URL, manifest.as_bytes()),
+                    "version": { "name": "17.0.8", "released": "2023-07-18T00:00:00+00:00" },
+                }],
+            },
+        })
+        .to_string();
+        let transport = MemoryTransport::new()
+            .with(Url::parse(JAVA_RUNTIME_INDEX_URL).unwrap(), index)
+            .with(Url::parse(MANIFEST_URL).unwrap(), manifest)
+            .with(Url::parse(FILE_URL).unwrap(), "evil");
+        Mcdl::with_transport(transport).with_retry(RetryPolicy::none())
+    }
+
+    fn file() -> Value {
+        json!({ "type": "file", "downloads": { "raw": info(FILE_URL, b"evil") } })
+    }
+
+    /// A fresh directory with a `dest` inside, so escapes land next to it
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mcdl-runtime-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("dest")).unwrap();
+        dir
+    }
+
+    async fn install(mcdl: &Mcdl, dest: &Path) -> Result<JavaRuntimeManifest> {
+        let platform = Platform::new(OsName::Linux, Arch::X86_64);
+        mcdl.install_java_runtime("java-runtime-gamma", &platform, dest)
+            .await
+    }
+
+    #[tokio::test]
+    async fn refuses_chained_links() {
+        let dir = scratch("chained");
+        let mcdl = mcdl(json!({
+            "a/b": { "type": "link", "target": ".." },
+            "c": { "type": "link", "target": "a/b/.." },
+            "c/evil": file(),
+        }));
+        let err = install(&mcdl, &dir.join("dest")).await.unwrap_err();
+        assert!(matches!(err, Error::UnsafePath(_)), "{err}");
+        assert!(!dir.join("evil").exists());
+        assert!(!dir.join("dest/c").exists());
+    }
+
+    #[tokio::test]
+    async fn refuses_files_under_declared_links() {
+        let dir = scratch("declared");
+        let mcdl = mcdl(json!({
+            "lib": { "type": "link", "target": "real" },
+            "lib/evil": file(),
+        }));
+        let err = install(&mcdl, &dir.join("dest")).await.unwrap_err();
+        assert!(matches!(err, Error::UnsafePath(_)), "{err}");
+        assert!(!dir.join("dest/real").exists());
+    }
+
+    #[tokio::test]
+    async fn refuses_links_out_of_dest() {
+        let dir = scratch("outside");
+        let mcdl = mcdl(json!({ "bin/java": { "type": "link", "target": "../../evil" } }));
+        let err = install(&mcdl, &dir.join("dest")).await.unwrap_err();
+        assert!(matches!(err, Error::UnsafePath(_)), "{err}");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn refuses_files_under_existing_symlinks() {
+        let dir = scratch("existing");
+        std::os::unix::fs::symlink(&dir, dir.join("dest/lib")).unwrap();
+        let mcdl = mcdl(json!({ "lib/evil": file() }));
+        let err = install(&mcdl, &dir.join("dest")).await.unwrap_err();
+        assert!(matches!(err, Error::UnsafePath(_)), "{err}");
+        assert!(!dir.join("evil").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn installs_files_and_links() {
+        let dir = scratch("valid");
+        let dest = dir.join("dest");
+        let mcdl = mcdl(json!({
+            "bin": { "type": "directory" },
+            "bin/java": file(),
+            "java": { "type": "link", "target": "bin/java" },
+        }));
+        install(&mcdl, &dest).await.unwrap();
+        assert_eq!(std::fs::read(dest.join("java")).unwrap(), b"evil");
+    }
+}
@@ -0,0 +1,229 @@
+use crate::verify::is_sha1;
+use crate::{Error, Result, fs};
+use bytes::Bytes;
+use std::collections::HashSet;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// How blobs are placed at their destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LinkMode {
+    /// Share the blob's inode, falling back to a copy across filesystems
+    #[default]
+    Hardlink,
+    /// Copy-on-write clone where the filesystem supports it, else a copy
+    Reflink,
+    Copy,
+}
+
+/// Content-addressed store of downloaded files, keyed by sha1.
+///
+/// Blobs live at `<dir>/<first two hex digits>/<sha1>` and are only written
+/// after their digest was verified, so a blob's name always matches its bytes.
+/// Installations are materialised from the store with [`LinkMode`], so files
+/// shared between versions take up space once.
+///
+/// The free-standing `download` methods on the manifest types fill the store
+/// too once a [`Mcdl`](crate::Mcdl) with one is made the default with
+/// [`Mcdl::set_shared`](crate::Mcdl::set_shared).
+#[derive(Debug, Clone)]
+pub struct BlobStore {
+    dir: PathBuf,
+    mode: LinkMode,
+}
+
+/// What a [`BlobStore::gc`] pass removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GcStats {
+    pub removed: usize,
+    pub freed_bytes: u64,
+}
+
+impl BlobStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            mode: LinkMode::default(),
+        }
+    }
+
+    pub fn link_mode(mut self, mode: LinkMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Where the blob `sha1` lives, failing with [`Error::InvalidSha1`] unless
+    /// it is 40 hex digits
+    pub fn path(&self, sha1: &str) -> Result<PathBuf> {
+        if !is_sha1(sha1) {
+            return Err(Error::InvalidSha1(sha1.to_owned()));
+        }
+        let sha1 = sha1.to_ascii_lowercase();
+        Ok(self.dir.join(&sha1[..2]).join(sha1))
+    }
+
+    pub async fn contains(&self, sha1: &str) -> Result<bool> {
+        Ok(tokio::fs::try_exists(self.path(sha1)?).await?)
+    }
+
+    pub async fn get(&self, sha1: &str) -> Result<Option<Bytes>> {
+        match tokio::fs::read(self.path(sha1)?).await {
+            Ok(bytes) => Ok(Some(bytes.into())),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Store bytes whose digest the caller already verified
+    pub(crate) async fn insert(&self, sha1: &str, bytes: &[u8]) -> Result<()> {
+        fs::write_atomic(&self.path(sha1)?, bytes).await
+    }
+
+    /// Place the blob `sha1` at `dest`, replacing whatever is there
+    pub async fn materialize(&self, sha1: &str, dest: &Path) -> Result<()> {
+        let blob = self.path(sha1)?;
+        if let Some(parent) = dest.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        // Link next to the destination and rename over it, so a hardlink never
+        // lets a later write through `dest` modify the blob itself
+        let temporary = fs::with_suffix(dest, ".tmp");
+        let _ = tokio::fs::remove_file(&temporary).await;
+        let mode = self.mode;
+        let (from, to) = (blob.clone(), temporary.clone());
+        tokio::task::spawn_blocking(move || place(mode, &from, &to))
+            .await
+            .map_err(std::io::Error::other)??;
+        tokio::fs::rename(&temporary, dest).await?;
+        Ok(())
+    }
+
+    /// Remove every blob whose sha1 is not in `referenced`, in any case.
+    /// Anything else in the store, such as the `.part` file of a download in
+    /// progress, is left alone.
+    pub async fn gc(&self, referenced: &HashSet<String>) -> Result<GcStats> {
+        let referenced: HashSet<_> = referenced
+            .iter()
+            .map(|sha1| sha1.to_ascii_lowercase())
+            .collect();
+        let mut stats = GcStats::default();
+        let mut buckets = match tokio::fs::read_dir(&self.dir).await {
+            Ok(buckets) => buckets,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(stats),
+            Err(err) => return Err(err.into()),
+        };
+        while let Some(bucket) = buckets.next_entry().await? {
+            if !bucket.file_type().await?.is_dir() {
+                continue;
+            }
+            let mut blobs = tokio::fs::read_dir(bucket.path()).await?;
+            while let Some(blob) = blobs.next_entry().await? {
+                let name = blob.file_name();
+                let Some(sha1) = name.to_str().filter(|name| is_sha1(name)) else {
+                    continue;
+                };
+                if referenced.contains(&sha1.to_ascii_lowercase()) {
+                    continue;
+                }
+                stats.freed_bytes += blob.metadata().await?.len();
+                tokio::fs::remove_file(blob.path()).await?;
+                stats.removed += 1;
+            }
+        }
+        Ok(stats)
+    }
+}
+
+fn place(mode: LinkMode, from: &Path, to: &Path) -> std::io::Result<()> {
+    match mode {
+        LinkMode::Hardlink => std::fs::hard_link(from, to).or_else(|_| copy(from, to)),
+        LinkMode::Reflink => reflink_copy::reflink_or_copy(from, to).map(|_| ()),
+        LinkMode::Copy => copy(from, to),
+    }
+}
+
+fn copy(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::copy(from, to).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HELLO: &str = "aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d";
+    const EMPTY: &str = "da39a3ee5e6b4b0d3255bfef95601890afd80709";
+
+    /// A store in a fresh directory holding `hello` and an empty blob
+    async fn store(name: &str) -> BlobStore {
+        let dir = std::env::temp_dir().join(format!("mcdl-store-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = BlobStore::new(dir);
+        store.insert(HELLO, b"hello").await.unwrap();
+        store.insert(EMPTY, b"").await.unwrap();
+        store
+    }
+
+    #[test]
+    fn rejects_malformed_digests() {
+        let store = BlobStore::new("store");
+        for sha1 in ["", "ab", "../../etc/passwd", &"é".repeat(20)] {
+            assert!(
+                matches!(store.path(sha1), Err(Error::InvalidSha1(_))),
+                "{sha1}"
+            );
+        }
+        assert_eq!(
+            store.path(&HELLO.to_ascii_uppercase()).unwrap(),
+            Path::new("store").join("aa").join(HELLO)
+        );
+    }
+
+    #[tokio::test]
+    async fn gc_keeps_referenced_blobs_in_any_case() {
+        let store = store("gc").await;
+        let referenced = HashSet::from([EMPTY.to_ascii_uppercase()]);
+        let stats = store.gc(&referenced).await.unwrap();
+        assert_eq!(
+            stats,
+            GcStats {
+                removed: 1,
+                freed_bytes: 5
+            }
+        );
+        assert!(store.contains(EMPTY).await.unwrap());
+        assert!(!store.contains(HELLO).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn gc_spares_files_in_flight() {
+        let store = store("in-flight").await;
+        let part = fs::with_suffix(&store.path(HELLO).unwrap(), ".part");
+        let temporary = fs::with_suffix(&store.path(EMPTY).unwrap(), ".tmp");
+        tokio::fs::write(&part, b"hel").await.unwrap();
+        tokio::fs::write(&temporary, b"").await.unwrap();
+        let stats = store.gc(&HashSet::new()).await.unwrap();
+        assert_eq!(stats.removed, 2);
+        assert!(part.exists());
+        assert!(temporary.exists());
+    }
+
+    #[tokio::test]
+    async fn materializes_in_every_mode() {
+        let store = store("materialize").await;
+        for mode in [LinkMode::Hardlink, LinkMode::Reflink, LinkMode::Copy] {
+            let store = store.clone().link_mode(mode);
+            let dest = store.dir().join("out").join(format!("{mode:?}.txt"));
+            std::fs::create_dir_all(dest.parent().unwrap()).unwrap();
+            std::fs::write(&dest, b"stale").unwrap();
+
+            // An existing file is replaced rather than written through
+            store.materialize(HELLO, &dest).await.unwrap();
+            assert_eq!(std::fs::read(&dest).unwrap(), b"hello", "{mode:?}");
+            assert_eq!(std::fs::read(store.path(HELLO).unwrap()).unwrap(), b"hello");
+        }
+    }
+}
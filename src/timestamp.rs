@@ -0,0 +1,20 @@
+//! Mojang writes timestamps as `2023-12-07T12:56:20+00:00` rather than chrono's
+//! default `Z` suffix, so serialize them the same way to round-trip manifests.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serializer};
+
+const FORMAT: &str = "%Y-%m-%dT%H:%M:%S%:z";
+
+pub(crate) fn serialize<S: Serializer>(
+    time: &DateTime<Utc>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&time.format(FORMAT))
+}
+
+pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<DateTime<Utc>, D::Error> {
+    DateTime::deserialize(deserializer)
+}
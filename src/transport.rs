@@ -0,0 +1,402 @@
+use crate::{Error, Result};
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_util::{StreamExt, stream};
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RANGE};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::RwLock;
+use url::Url;
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+pub type ByteStream = Pin<Box<dyn Stream<Item = Result<Bytes>> + Send>>;
+
+/// How [`Mcdl`](crate::Mcdl) fetches the contents of a URL.
+///
+/// Implementations report missing resources and other non-success responses as
+/// [`Error::Status`]. Integrity checks happen above this layer, so a transport
+/// only has to move bytes.
+pub trait Transport: fmt::Debug + Send + Sync {
+    fn get_bytes<'a>(&'a self, url: &'a Url) -> BoxFuture<'a, Result<Bytes>>;
+
+    fn get_stream<'a>(&'a self, url: &'a Url) -> BoxFuture<'a, Result<ByteStream>>;
+
+    /// Stream `url` starting at byte `offset`, for resuming a download.
+    ///
+    /// Returns the offset the stream really starts at, which is `0` when the
+    /// range could not be honoured. The default implementation never honours it.
+    fn get_stream_from<'a>(
+        &'a self,
+        url: &'a Url,
+        offset: u64,
+    ) -> BoxFuture<'a, Result<(u64, ByteStream)>> {
+        let _ = offset;
+        Box::pin(async move { Ok((0, self.get_stream(url).await?)) })
+    }
+
+    /// Fetch `url` unless it still matches `validators` from an earlier fetch.
+    ///
+    /// The default implementation always fetches the full body.
+    fn get_conditional<'a>(
+        &'a self,
+        url: &'a Url,
+        validators: &'a Validators,
+    ) -> BoxFuture<'a, Result<Conditional>> {
+        let _ = validators;
+        Box::pin(async move {
+            Ok(Conditional::Modified {
+                body: self.get_bytes(url).await?,
+                validators: Validators::default(),
+            })
+        })
+    }
+}
+
+/// HTTP cache validators remembered from a previous response
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct Validators {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub etag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub last_modified: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conditional {
+    NotModified,
+    Modified { body: Bytes, validators: Validators },
+}
+
+/// The default transport, backed by a pooled [`reqwest::Client`]
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    async fn get(&self, url: &Url) -> Result<Response> {
+        self.send(self.client.get(url.clone())).await
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(Error::Status {
+                url: response.url().clone(),
+                status,
+            });
+        }
+        Ok(response)
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn get_bytes<'a>(&'a self, url: &'a Url) -> BoxFuture<'a, Result<Bytes>> {
+        Box::pin(async move { Ok(self.get(url).await?.bytes().await?) })
+    }
+
+    fn get_stream<'a>(&'a self, url: &'a Url) -> BoxFuture<'a, Result<ByteStream>> {
+        Box::pin(async move { Ok(body_stream(self.get(url).await?)) })
+    }
+
+    fn get_stream_from<'a>(
+        &'a self,
+        url: &'a Url,
+        offset: u64,
+    ) -> BoxFuture<'a, Result<(u64, ByteStream)>> {
+        Box::pin(async move {
+            if offset == 0 {
+                return Ok((0, self.get_stream(url).await?));
+            }
+            let request = self
+                .client
+                .get(url.clone())
+                .header(RANGE, format!("bytes={offset}-"));
+            let response = self.send(request).await?;
+            let start = match response.status() {
+                StatusCode::PARTIAL_CONTENT => offset,
+                _ => 0,
+            };
+            Ok((start, body_stream(response)))
+        })
+    }
+
+    fn get_conditional<'a>(
+        &'a self,
+        url: &'a Url,
+        validators: &'a Validators,
+    ) -> BoxFuture<'a, Result<Conditional>> {
+        Box::pin(async move {
+            let mut request = self.client.get(url.clone());
+            if let Some(etag) = &validators.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &validators.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+            let response = request.send().await?;
+            let status = response.status();
+            if status == StatusCode::NOT_MODIFIED {
+                return Ok(Conditional::NotModified);
+            }
+            if !status.is_success() {
+                return Err(Error::Status {
+                    url: response.url().clone(),
+                    status,
+                });
+            }
+            let header = |name| {
+                response
+                    .headers()
+                    .get(name)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_owned)
+            };
+            let validators = Validators {
+                etag: header(ETAG),
+                last_modified: header(LAST_MODIFIED),
+            };
+            Ok(Conditional::Modified {
+                body: response.bytes().await?,
+                validators,
+            })
+        })
+    }
+}
+
+/// Serves fixed responses from memory, for running without network access.
+///
+/// URLs that were never inserted answer with `404 Not Found`.
+#[derive(Debug, Default)]
+pub struct MemoryTransport {
+    files: RwLock<HashMap<Url, Bytes>>,
+}
+
+impl MemoryTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&self, url: Url, body: impl Into<Bytes>) {
+        self.files.write().unwrap().insert(url, body.into());
+    }
+
+    pub fn with(self, url: Url, body: impl Into<Bytes>) -> Self {
+        self.insert(url, body);
+        self
+    }
+
+    fn lookup(&self, url: &Url) -> Result<Bytes> {
+        self.files
+            .read()
+            .unwrap()
+            .get(url)
+            .cloned()
+            .ok_or_else(|| not_found(url))
+    }
+}
+
+impl Transport for MemoryTransport {
+    fn get_bytes<'a>(&'a self, url: &'a Url) -> BoxFuture<'a, Result<Bytes>> {
+        Box::pin(async move { self.lookup(url) })
+    }
+
+    fn get_stream<'a>(&'a self, url: &'a Url) -> BoxFuture<'a, Result<ByteStream>> {
+        Box::pin(async move { Ok(single_chunk(self.lookup(url)?)) })
+    }
+
+    fn get_stream_from<'a>(
+        &'a self,
+        url: &'a Url,
+        offset: u64,
+    ) -> BoxFuture<'a, Result<(u64, ByteStream)>> {
+        Box::pin(async move { Ok(range(self.lookup(url)?, offset)) })
+    }
+}
+
+/// Serves files from a fixture directory laid out as `<root>/<host>/<path>`.
+///
+/// `https://piston-data.mojang.com/v1/objects/<sha1>/client.jar` for instance is
+/// read from `<root>/piston-data.mojang.com/v1/objects/<sha1>/client.jar`.
+/// Missing files answer with `404 Not Found`.
+#[derive(Debug, Clone)]
+pub struct DirectoryTransport {
+    root: PathBuf,
+}
+
+impl DirectoryTransport {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Where a URL is looked up on disk
+    pub fn path_for(&self, url: &Url) -> PathBuf {
+        let mut path = self.root.join(url.host_str().unwrap_or_default());
+        path.extend(url.path_segments().into_iter().flatten());
+        path
+    }
+
+    fn read(&self, url: &Url) -> Result<Bytes> {
+        match std::fs::read(self.path_for(url)) {
+            Ok(body) => Ok(body.into()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Err(not_found(url)),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+impl Transport for DirectoryTransport {
+    fn get_bytes<'a>(&'a self, url: &'a Url) -> BoxFuture<'a, Result<Bytes>> {
+        Box::pin(async move { self.read(url) })
+    }
+
+    fn get_stream<'a>(&'a self, url: &'a Url) -> BoxFuture<'a, Result<ByteStream>> {
+        Box::pin(async move { Ok(single_chunk(self.read(url)?)) })
+    }
+
+    fn get_stream_from<'a>(
+        &'a self,
+        url: &'a Url,
+        offset: u64,
+    ) -> BoxFuture<'a, Result<(u64, ByteStream)>> {
+        Box::pin(async move { Ok(range(self.read(url)?, offset)) })
+    }
+}
+
+fn body_stream(response: Response) -> ByteStream {
+    Box::pin(
+        response
+            .bytes_stream()
+            .map(|chunk| chunk.map_err(Error::from)),
+    )
+}
+
+/// Serve `body` from `offset`, or whole if the offset is past its end
+fn range(body: Bytes, offset: u64) -> (u64, ByteStream) {
+    match usize::try_from(offset) {
+        Ok(start) if start <= body.len() => (offset, single_chunk(body.slice(start..))),
+        _ => (0, single_chunk(body)),
+    }
+}
+
+fn single_chunk(body: Bytes) -> ByteStream {
+    Box::pin(stream::once(async move { Ok(body) }))
+}
+
+fn not_found(url: &Url) -> Error {
+    Error::Status {
+        url: url.clone(),
+        status: StatusCode::NOT_FOUND,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Arch, Mcdl, OsName, Platform, RetryPolicy};
+    use serde_json::json;
+    use sha1::{Digest, Sha1};
+
+    const LIBRARY_URL: &str = "https://libraries.minecraft.net/com/example/lib/1.0/lib-1.0.jar";
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    fn sha1(bytes: &[u8]) -> String {
+        format!("{:x}", Sha1::digest(bytes))
+    }
+
+    /// A root manifest listing `1.0`, whose version JSON has a single library
+    fn fixtures(library_url: &str) -> MemoryTransport {
+        let jar = b"library jar".to_vec();
+        let version = json!({
+            "id": "1.0",
+            "type": "release",
+            "mainClass": "net.minecraft.client.main.Main",
+            "releaseTime": "2024-01-01T00:00:00+00:00",
+            "time": "2024-01-01T00:00:00+00:00",
+            "downloads": {
+                "client": {
+                    "sha1": sha1(b"client"),
+                    "size": 6,
+                    "url": "https://piston-data.mojang.com/v1/objects/client.jar",
+                },
+            },
+            "libraries": [{
+                "name": "com.example:lib:1.0",
+                "downloads": {
+                    "artifact": {
+                        "path": "com/example/lib/1.0/lib-1.0.jar",
+                        "sha1": sha1(&jar),
+                        "size": jar.len(),
+                        "url": library_url,
+                    },
+                },
+            }],
+        })
+        .to_string();
+        let version_url = "https://piston-meta.mojang.com/v1/packages/1.0.json";
+        let root = json!({
+            "latest": { "release": "1.0", "snapshot": "1.0" },
+            "versions": [{
+                "id": "1.0",
+                "type": "release",
+                "url": version_url,
+                "time": "2024-01-01T00:00:00+00:00",
+                "releaseTime": "2024-01-01T00:00:00+00:00",
+                "sha1": sha1(version.as_bytes()),
+                "complianceLevel": 1,
+            }],
+        })
+        .to_string();
+        MemoryTransport::new()
+            .with(url(crate::MANIFEST_URL), root)
+            .with(url(version_url), version)
+            .with(url(LIBRARY_URL), jar)
+    }
+
+    async fn download_library(transport: MemoryTransport) -> Result<Option<Bytes>> {
+        let mcdl = Mcdl::with_transport(transport).with_retry(RetryPolicy::none());
+        let root = mcdl.fetch_root_manifest().await?;
+        let manifest = mcdl.fetch_version_manifest(root.version("1.0")?).await?;
+        let platform = Platform::new(OsName::Linux, Arch::X86_64);
+        let downloaded = mcdl
+            .download_library_for(&manifest.libraries[0], &platform)
+            .await?;
+        Ok(downloaded
+            .and_then(|(artifact, _)| artifact)
+            .map(|(_, bytes)| bytes))
+    }
+
+    #[tokio::test]
+    async fn downloads_a_library_from_the_root_manifest() {
+        let jar = download_library(fixtures(LIBRARY_URL)).await.unwrap();
+        assert_eq!(jar.as_deref(), Some(&b"library jar"[..]));
+    }
+
+    #[tokio::test]
+    async fn missing_files_answer_not_found() {
+        let missing = "https://libraries.minecraft.net/com/example/missing.jar";
+        let err = download_library(fixtures(missing)).await.unwrap_err();
+        assert!(
+            matches!(&err, Error::Status { url, status }
+                if url.as_str() == missing && *status == StatusCode::NOT_FOUND),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use crate::{ByteStream, Error, Mismatch, Result};
+use futures_util::{StreamExt, stream};
+use sha1::{Digest, Sha1};
+use url::Url;
+
+/// Incrementally checks downloaded bytes against the sha1 and size from a manifest
+#[derive(Clone)]
+pub(crate) struct Verifier {
+    url: Url,
+    hasher: Sha1,
+    sha1: String,
+    size: Option<u64>,
+    len: u64,
+}
+
+impl Verifier {
+    pub(crate) fn new(url: &Url, sha1: &str, size: Option<u64>) -> Self {
+        Self {
+            url: url.clone(),
+            hasher: Sha1::new(),
+            sha1: sha1.to_ascii_lowercase(),
+            size,
+            len: 0,
+        }
+    }
+
+    /// Feed a chunk, failing early once more bytes arrived than expected
+    pub(crate) fn update(&mut self, chunk: &[u8]) -> Result<()> {
+        self.hasher.update(chunk);
+        self.len += chunk.len() as u64;
+        match self.size {
+            Some(expected) if self.len > expected => Err(self.mismatch(Mismatch::Size {
+                expected,
+                actual: self.len,
+            })),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn finish(mut self) -> Result<()> {
+        if let Some(expected) = self.size
+            && self.len != expected
+        {
+            return Err(self.mismatch(Mismatch::Size {
+                expected,
+                actual: self.len,
+            }));
+        }
+        let actual = format!("{:x}", self.hasher.finalize_reset());
+        if actual != self.sha1 {
+            return Err(self.mismatch(Mismatch::Sha1 {
+                expected: self.sha1.clone(),
+                actual,
+            }));
+        }
+        Ok(())
+    }
+
+    fn mismatch(&self, mismatch: Mismatch) -> Error {
+        Error::Integrity {
+            url: self.url.clone(),
+            mismatch: Box::new(mismatch),
+        }
+    }
+}
+
+/// Whether `sha1` is 40 hex digits, and so also safe to use as a file name
+pub(crate) fn is_sha1(sha1: &str) -> bool {
+    sha1.len() == 40 && sha1.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+/// Verify a fully downloaded body
+pub(crate) fn verify(url: &Url, bytes: &[u8], sha1: &str, size: Option<u64>) -> Result<()> {
+    let mut verifier = Verifier::new(url, sha1, size);
+    verifier.update(bytes)?;
+    verifier.finish()
+}
+
+struct Verifying {
+    inner: ByteStream,
+    verifier: Option<Verifier>,
+    pending: Option<bytes::Bytes>,
+}
+
+/// Wrap a byte stream so that it is hashed as it goes.
+///
+/// One chunk is held back at all times, so a mismatch replaces the final chunk
+/// with an error and a consumer never sees a complete but corrupt body.
+pub(crate) fn verify_stream(inner: ByteStream, verifier: Verifier) -> ByteStream {
+    let state = Verifying {
+        inner,
+        verifier: Some(verifier),
+        pending: None,
+    };
+    Box::pin(stream::unfold(state, |mut state| async move {
+        loop {
+            let verifier = state.verifier.as_mut()?;
+            match state.inner.next().await {
+                Some(Ok(chunk)) => {
+                    if let Err(err) = verifier.update(&chunk) {
+                        state.verifier = None;
+                        return Some((Err(err), state));
+                    }
+                    if let Some(previous) = state.pending.replace(chunk) {
+                        return Some((Ok(previous), state));
+                    }
+                }
+                Some(Err(err)) => {
+                    state.verifier = None;
+                    return Some((Err(err), state));
+                }
+                None => {
+                    let verifier = state.verifier.take()?;
+                    return match verifier.finish() {
+                        Ok(()) => state.pending.take().map(|chunk| (Ok(chunk), state)),
+                        Err(err) => Some((Err(err), state)),
+                    };
+                }
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+
+    /// Run `chunks` through [`verify_stream`] expecting `expected`
+    async fn verify_chunks(chunks: &'static [&'static str], expected: &[u8]) -> Vec<Result<Bytes>> {
+        let url = Url::parse("https://example.com/file").unwrap();
+        let sha1 = format!("{:x}", Sha1::digest(expected));
+        let verifier = Verifier::new(&url, &sha1, Some(expected.len() as u64));
+        let inner = stream::iter(
+            chunks
+                .iter()
+                .map(|chunk| Ok(Bytes::from_static(chunk.as_bytes()))),
+        );
+        verify_stream(Box::pin(inner), verifier).collect().await
+    }
+
+    fn chunks(items: &[Result<Bytes>]) -> Vec<&[u8]> {
+        items
+            .iter()
+            .map_while(|item| item.as_ref().ok())
+            .map(|chunk| &chunk[..])
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn passes_a_matching_body_through() {
+        let items = verify_chunks(&["hel", "lo"], b"hello").await;
+        assert_eq!(items.len(), 2);
+        assert_eq!(chunks(&items), [&b"hel"[..], b"lo"]);
+    }
+
+    #[tokio::test]
+    async fn replaces_the_last_chunk_on_a_sha1_mismatch() {
+        let items = verify_chunks(&["hel", "lp"], b"hello").await;
+        assert_eq!(items.len(), 2);
+        assert_eq!(chunks(&items), [b"hel"]);
+        assert!(matches!(
+            &items[1],
+            Err(Error::Integrity { mismatch, .. }) if matches!(**mismatch, Mismatch::Sha1 { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn fails_an_oversize_body_early() {
+        let items = verify_chunks(&["hel", "lo world", "never read"], b"hello").await;
+        assert_eq!(items.len(), 1);
+        assert!(matches!(
+            &items[0],
+            Err(Error::Integrity { mismatch, .. })
+                if matches!(**mismatch, Mismatch::Size { expected: 5, actual: 11 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn accepts_an_empty_body() {
+        assert!(verify_chunks(&[], b"").await.is_empty());
+        let items = verify_chunks(&[], b"hello").await;
+        assert!(matches!(
+            &items[..],
+            [Err(Error::Integrity { mismatch, .. })]
+                if matches!(**mismatch, Mismatch::Size { expected: 5, actual: 0 })
+        ));
+    }
+}
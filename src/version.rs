@@ -0,0 +1,598 @@
+use crate::{ReleaseKind, Result, RootManifest, VersionRelease};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+static RELEASE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d+)\.(\d+)(?:\.(\d+))?$").unwrap());
+static PRE_RELEASE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d+\.\d+(?:\.\d+)?)(?:-pre-?| Pre-Release )(\d+)$").unwrap());
+static RELEASE_CANDIDATE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d+\.\d+(?:\.\d+)?)-rc-?(\d+)$").unwrap());
+static RELEASE_SNAPSHOT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d+\.\d+(?:\.\d+)?)-snapshot-(\d+)$").unwrap());
+static SNAPSHOT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d{2})w(\d{2})([a-z].*)$").unwrap());
+
+/// A Minecraft version id, such as `1.20.4`, `23w45a` or `b1.7.3`.
+///
+/// Ids compare semantically where their forms allow it: releases by number
+/// with their snapshots, pre-releases and release candidates before them,
+/// weekly snapshots by year, week and build, and the old eras in the order
+/// pre-classic, classic, infdev, alpha, beta, then everything since 1.0. A
+/// weekly snapshot and a release cannot be compared from their ids alone, use
+/// [`RootManifest::compare`] for that.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub struct VersionId {
+    id: String,
+    form: VersionForm,
+}
+
+/// What a [`VersionId`] turned out to be
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum VersionForm {
+    /// `1.20.4`
+    Release(ReleaseNumber),
+    /// `1.20.4-pre1` or `26.1-pre-1`, and `1.14 Pre-Release 1` before 1.14.3
+    PreRelease(ReleaseNumber, u32),
+    /// `1.20.4-rc1` or `26.1-rc-1`
+    ReleaseCandidate(ReleaseNumber, u32),
+    /// `26.1-snapshot-1`, how snapshots are named since they carry their release
+    ReleaseSnapshot(ReleaseNumber, u32),
+    /// `23w45a`, also used for some April Fools' versions like `20w14infinite`
+    Snapshot { year: u32, week: u32, build: String },
+    /// `1.14.3 - Combat Test` and the other combat test builds
+    CombatTest,
+    /// `b1.7.3`, without the prefix
+    Beta(String),
+    /// `a1.2.6`, without the prefix
+    Alpha(String),
+    /// `inf-20100618`, without the prefix
+    Infdev(String),
+    /// `c0.30_01c`, without the prefix
+    Classic(String),
+    /// `rd-132211`, without the prefix
+    PreClassic(String),
+    /// Anything else, such as `3D Shareware v1.34`
+    Other,
+}
+
+/// The `major.minor.patch` of a release, where `1.20` has patch 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReleaseNumber {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl VersionId {
+    pub fn parse(id: &str) -> Self {
+        Self {
+            id: id.to_owned(),
+            form: VersionForm::parse(id),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.id
+    }
+
+    pub fn form(&self) -> &VersionForm {
+        &self.form
+    }
+
+    /// The release this is, or is a snapshot, pre-release or release candidate of
+    pub fn release(&self) -> Option<ReleaseNumber> {
+        match self.form {
+            VersionForm::Release(number)
+            | VersionForm::PreRelease(number, _)
+            | VersionForm::ReleaseCandidate(number, _)
+            | VersionForm::ReleaseSnapshot(number, _) => Some(number),
+            _ => None,
+        }
+    }
+
+    pub fn is_snapshot(&self) -> bool {
+        matches!(
+            self.form,
+            VersionForm::Snapshot { .. } | VersionForm::ReleaseSnapshot(..)
+        )
+    }
+}
+
+impl VersionForm {
+    fn parse(id: &str) -> Self {
+        let number = |captures: &regex::Captures<'_>, group| {
+            captures
+                .get(group)
+                .map_or(Some(0), |digits| digits.as_str().parse().ok())
+        };
+        if let Some(captures) = RELEASE.captures(id)
+            && let (Some(major), Some(minor), Some(patch)) = (
+                number(&captures, 1),
+                number(&captures, 2),
+                number(&captures, 3),
+            )
+        {
+            return Self::Release(ReleaseNumber {
+                major,
+                minor,
+                patch,
+            });
+        }
+        for (pattern, form) in [
+            (&PRE_RELEASE, Self::PreRelease as fn(_, _) -> _),
+            (&RELEASE_CANDIDATE, Self::ReleaseCandidate),
+            (&RELEASE_SNAPSHOT, Self::ReleaseSnapshot),
+        ] {
+            if let Some(captures) = pattern.captures(id)
+                && let Self::Release(release) = Self::parse(&captures[1])
+                && let Some(n) = number(&captures, 2)
+            {
+                return form(release, n);
+            }
+        }
+        if let Some(captures) = SNAPSHOT.captures(id)
+            && let (Some(year), Some(week)) = (number(&captures, 1), number(&captures, 2))
+        {
+            return Self::Snapshot {
+                year,
+                week,
+                build: captures[3].to_owned(),
+            };
+        }
+        if id.to_ascii_lowercase().contains("combat") {
+            return Self::CombatTest;
+        }
+        let old = [
+            ("rd-", Self::PreClassic as fn(String) -> Self),
+            ("c", Self::Classic),
+            ("inf-", Self::Infdev),
+            ("a", Self::Alpha),
+            ("b", Self::Beta),
+        ];
+        for (prefix, form) in old {
+            if let Some(rest) = id.strip_prefix(prefix)
+                && rest.starts_with(|c: char| c.is_ascii_digit())
+            {
+                return form(rest.to_owned());
+            }
+        }
+        Self::Other
+    }
+
+    /// Position in Minecraft's history, for comparing across eras
+    fn era(&self) -> Option<u8> {
+        Some(match self {
+            Self::PreClassic(_) => 0,
+            Self::Classic(_) => 1,
+            Self::Infdev(_) => 2,
+            Self::Alpha(_) => 3,
+            Self::Beta(_) => 4,
+            Self::Release(_)
+            | Self::PreRelease(..)
+            | Self::ReleaseCandidate(..)
+            | Self::ReleaseSnapshot(..)
+            | Self::Snapshot { .. }
+            | Self::CombatTest => 5,
+            Self::Other => return None,
+        })
+    }
+
+    fn semantic_cmp(&self, other: &Self) -> Option<Ordering> {
+        let (era, other_era) = (self.era()?, other.era()?);
+        if era != other_era {
+            return Some(era.cmp(&other_era));
+        }
+        match (self, other) {
+            (
+                Self::Snapshot { year, week, build },
+                Self::Snapshot {
+                    year: other_year,
+                    week: other_week,
+                    build: other_build,
+                },
+            ) => Some(
+                (year, week)
+                    .cmp(&(other_year, other_week))
+                    .then_with(|| natural_cmp(build, other_build)),
+            ),
+            (Self::PreClassic(a), Self::PreClassic(b))
+            | (Self::Classic(a), Self::Classic(b))
+            | (Self::Infdev(a), Self::Infdev(b))
+            | (Self::Alpha(a), Self::Alpha(b))
+            | (Self::Beta(a), Self::Beta(b)) => Some(natural_cmp(a, b)),
+            _ => Some(self.release_stage()?.cmp(&other.release_stage()?)),
+        }
+    }
+
+    /// Sort key among a release and its snapshots, pre-releases and release
+    /// candidates
+    fn release_stage(&self) -> Option<(ReleaseNumber, u8, u32)> {
+        match *self {
+            Self::ReleaseSnapshot(number, n) => Some((number, 0, n)),
+            Self::PreRelease(number, n) => Some((number, 1, n)),
+            Self::ReleaseCandidate(number, n) => Some((number, 2, n)),
+            Self::Release(number) => Some((number, 3, 0)),
+            _ => None,
+        }
+    }
+}
+
+impl PartialOrd for VersionId {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.id == other.id {
+            return Some(Ordering::Equal);
+        }
+        // Differently spelled ids of the same version are not the same id
+        self.form
+            .semantic_cmp(&other.form)
+            .filter(|ordering| ordering.is_ne())
+    }
+}
+
+impl FromStr for VersionId {
+    type Err = Infallible;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        Ok(Self::parse(id))
+    }
+}
+
+impl From<String> for VersionId {
+    fn from(id: String) -> Self {
+        Self {
+            form: VersionForm::parse(&id),
+            id,
+        }
+    }
+}
+
+impl From<VersionId> for String {
+    fn from(id: VersionId) -> Self {
+        id.id
+    }
+}
+
+impl fmt::Display for VersionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.id)
+    }
+}
+
+impl fmt::Display for ReleaseNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)?;
+        if self.patch != 0 {
+            write!(f, ".{}", self.patch)?;
+        }
+        Ok(())
+    }
+}
+
+impl VersionRelease {
+    pub fn version_id(&self) -> VersionId {
+        VersionId::parse(&self.id)
+    }
+}
+
+impl RootManifest {
+    /// Order two versions semantically where their ids allow it, and by
+    /// release time otherwise.
+    ///
+    /// A snapshot sorts right before the first pre-release of the release it
+    /// leads to, see [`release_for`](Self::release_for), so `20w45a` comes after
+    /// `1.16.5` even though it was published earlier.
+    pub fn compare(&self, a: &str, b: &str) -> Result<Ordering> {
+        let (id_a, id_b) = (VersionId::parse(a), VersionId::parse(b));
+        if let Some(ordering) = id_a.partial_cmp(&id_b) {
+            return Ok(ordering);
+        }
+        match (id_a.release(), id_b.release()) {
+            (None, Some(number)) if id_a.is_snapshot() => {
+                if let Some(target) = self.release_for(a)? {
+                    return Ok(snapshot_cmp(target, number));
+                }
+            }
+            (Some(number), None) if id_b.is_snapshot() => {
+                if let Some(target) = self.release_for(b)? {
+                    return Ok(snapshot_cmp(target, number).reverse());
+                }
+            }
+            _ => {}
+        }
+        Ok(self
+            .version(a)?
+            .release_time
+            .cmp(&self.version(b)?.release_time))
+    }
+
+    /// The release a snapshot, pre-release or release candidate leads up to.
+    ///
+    /// Pre-releases, release candidates and snapshots like `26.1-snapshot-1`
+    /// name their release. For weekly snapshots this is the next release
+    /// published after them, skipping hotfixes that were published while
+    /// development of the next version was underway, such as 1.16.5 during the
+    /// 1.17 snapshots. A hotfix is recognised by having no pre-releases,
+    /// patching an already released version, and being followed directly by
+    /// more snapshots. Returns `None` for versions from before 1.0 and for
+    /// snapshots whose release has not come out yet.
+    pub fn release_for(&self, id: &str) -> Result<Option<&VersionRelease>> {
+        let version = self.version(id)?;
+        if let Some(number) = version.version_id().release() {
+            return Ok(self.release(number));
+        }
+        if matches!(version.kind, ReleaseKind::OldBeta | ReleaseKind::OldAlpha) {
+            return Ok(None);
+        }
+
+        let versions = self.query().run()?;
+        let later = versions
+            .iter()
+            .filter(|later| later.release_time > version.release_time);
+        for candidate in later {
+            let Some(number) = candidate.version_id().release() else {
+                continue;
+            };
+            let Some(release) = self.release(number) else {
+                continue;
+            };
+            if !self.is_hotfix(release, version, &versions) {
+                return Ok(Some(release));
+            }
+        }
+        Ok(None)
+    }
+
+    fn release(&self, number: ReleaseNumber) -> Option<&VersionRelease> {
+        let id = number.to_string();
+        self.versions
+            .iter()
+            .find(|version| version.kind == ReleaseKind::Release && version.id == id)
+    }
+
+    /// Whether `release` is a hotfix published between `snapshot` and the
+    /// release it really leads to
+    fn is_hotfix(
+        &self,
+        release: &VersionRelease,
+        snapshot: &VersionRelease,
+        chronological: &[&VersionRelease],
+    ) -> bool {
+        let Some(number) = release.version_id().release() else {
+            return false;
+        };
+        let line_released = self.versions.iter().any(|version| {
+            version.kind == ReleaseKind::Release
+                && version.release_time < snapshot.release_time
+                && version
+                    .version_id()
+                    .release()
+                    .is_some_and(|other| (other.major, other.minor) == (number.major, number.minor))
+        });
+        let has_pre_release = self.versions.iter().any(|version| {
+            matches!(version.version_id().form, VersionForm::PreRelease(of, _) if of == number)
+        });
+        let followed_by_snapshot = chronological
+            .iter()
+            .find(|version| version.release_time > release.release_time)
+            .is_some_and(|next| next.version_id().is_snapshot());
+        line_released && !has_pre_release && followed_by_snapshot
+    }
+}
+
+/// Order a snapshot leading to `target` against a version of release `number`
+fn snapshot_cmp(target: &VersionRelease, number: ReleaseNumber) -> Ordering {
+    match target.version_id().release() {
+        Some(target) if number < target => Ordering::Greater,
+        _ => Ordering::Less,
+    }
+}
+
+/// Compare strings chunk by chunk, with runs of digits compared as numbers
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (chunk_a, rest_a) = split_chunk(a);
+        let (chunk_b, rest_b) = split_chunk(b);
+        let ordering = match (chunk_a.parse::<u64>(), chunk_b.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            _ => chunk_a.cmp(chunk_b),
+        };
+        if ordering.is_ne() || chunk_a.is_empty() {
+            return ordering;
+        }
+        (a, b) = (rest_a, rest_b);
+    }
+}
+
+/// Split off a leading run of digits, or of anything else
+fn split_chunk(s: &str) -> (&str, &str) {
+    let digits = s.starts_with(|c: char| c.is_ascii_digit());
+    let end = s
+        .find(|c: char| c.is_ascii_digit() != digits)
+        .unwrap_or(s.len());
+    s.split_at(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn number(major: u32, minor: u32, patch: u32) -> ReleaseNumber {
+        ReleaseNumber {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// A root manifest listing `(id, type, release date)` in any order
+    fn manifest(versions: &[(&str, &str, &str)]) -> RootManifest {
+        let versions: Vec<_> = versions
+            .iter()
+            .map(|(id, kind, date)| {
+                json!({
+                    "id": id,
+                    "type": kind,
+                    "url": format!("https://piston-meta.mojang.com/v1/packages/{id}.json"),
+                    "time": format!("{date}T00:00:00+00:00"),
+                    "releaseTime": format!("{date}T00:00:00+00:00"),
+                    "sha1": "0".repeat(40),
+                    "complianceLevel": 1,
+                })
+            })
+            .collect();
+        let root = json!({
+            "latest": { "release": "1.17", "snapshot": "26.1-pre-1" },
+            "versions": versions,
+        });
+        crate::from_json(root.to_string().as_bytes()).unwrap()
+    }
+
+    fn around_1_17() -> RootManifest {
+        manifest(&[
+            ("1.16.4", "release", "2020-11-02"),
+            ("20w45a", "snapshot", "2020-11-04"),
+            ("20w46a", "snapshot", "2020-11-11"),
+            ("1.16.5-rc1", "snapshot", "2021-01-13"),
+            ("1.16.5", "release", "2021-01-15"),
+            ("21w03a", "snapshot", "2021-01-20"),
+            ("1.17-pre1", "snapshot", "2021-05-27"),
+            ("1.17", "release", "2021-06-08"),
+            ("26.1-snapshot-1", "snapshot", "2025-12-16"),
+            ("26.1-pre-1", "snapshot", "2026-02-24"),
+        ])
+    }
+
+    #[test]
+    fn parses_every_form() {
+        let snapshot = |year, week, build: &str| VersionForm::Snapshot {
+            year,
+            week,
+            build: build.to_owned(),
+        };
+        let cases = [
+            ("1.20.4", VersionForm::Release(number(1, 20, 4))),
+            ("1.20", VersionForm::Release(number(1, 20, 0))),
+            ("1.20.4-pre1", VersionForm::PreRelease(number(1, 20, 4), 1)),
+            (
+                "1.14 Pre-Release 2",
+                VersionForm::PreRelease(number(1, 14, 0), 2),
+            ),
+            (
+                "1.20.4-rc1",
+                VersionForm::ReleaseCandidate(number(1, 20, 4), 1),
+            ),
+            (
+                "26.1-snapshot-1",
+                VersionForm::ReleaseSnapshot(number(26, 1, 0), 1),
+            ),
+            ("26.1-pre-1", VersionForm::PreRelease(number(26, 1, 0), 1)),
+            (
+                "26.1-rc-1",
+                VersionForm::ReleaseCandidate(number(26, 1, 0), 1),
+            ),
+            ("23w45a", snapshot(23, 45, "a")),
+            ("20w14infinite", snapshot(20, 14, "infinite")),
+            ("b1.7.3", VersionForm::Beta("1.7.3".to_owned())),
+            ("a1.2.6", VersionForm::Alpha("1.2.6".to_owned())),
+            ("inf-20100618", VersionForm::Infdev("20100618".to_owned())),
+            ("c0.30_01c", VersionForm::Classic("0.30_01c".to_owned())),
+            ("rd-132211", VersionForm::PreClassic("132211".to_owned())),
+            ("1.14.3 - Combat Test", VersionForm::CombatTest),
+            ("3D Shareware v1.34", VersionForm::Other),
+        ];
+        for (id, form) in cases {
+            assert_eq!(VersionId::parse(id).form(), &form, "{id}");
+        }
+    }
+
+    #[test]
+    fn orders_by_id() {
+        let ascending = [
+            "rd-132211",
+            "c0.0.11a",
+            "c0.30_01c",
+            "inf-20100618",
+            "a1.0.4",
+            "a1.2.6",
+            "b1.7.3",
+            "1.0",
+            "1.14 Pre-Release 2",
+            "1.14",
+            "1.20.4-pre1",
+            "1.20.4-pre2",
+            "1.20.4-rc1",
+            "1.20.4",
+            "1.20.10",
+            "26.1-snapshot-1",
+            "26.1-snapshot-10",
+            "26.1-pre-1",
+            "26.1-rc-1",
+            "26.1",
+        ];
+        for pair in ascending.windows(2) {
+            let (a, b) = (VersionId::parse(pair[0]), VersionId::parse(pair[1]));
+            assert!(a < b, "{a} < {b}");
+            assert!(b > a, "{b} > {a}");
+        }
+        let snapshots = ["20w45a", "20w45b", "20w46a", "21w03a"];
+        for pair in snapshots.windows(2) {
+            assert!(VersionId::parse(pair[0]) < VersionId::parse(pair[1]));
+        }
+    }
+
+    #[test]
+    fn leaves_weekly_snapshots_and_releases_unordered() {
+        let (snapshot, release) = (VersionId::parse("20w45a"), VersionId::parse("1.16.5"));
+        assert_eq!(snapshot.partial_cmp(&release), None);
+        let other = VersionId::parse("3D Shareware v1.34");
+        assert_eq!(other.partial_cmp(&release), None);
+    }
+
+    #[test]
+    fn finds_the_release_of_a_snapshot() {
+        let root = around_1_17();
+        let release_for = |id| root.release_for(id).unwrap().map(|release| &*release.id);
+        // 1.16.5 is a hotfix that came out during the 1.17 snapshots
+        assert_eq!(release_for("20w45a"), Some("1.17"));
+        assert_eq!(release_for("21w03a"), Some("1.17"));
+        assert_eq!(release_for("1.16.5-rc1"), Some("1.16.5"));
+        assert_eq!(release_for("1.17-pre1"), Some("1.17"));
+        assert_eq!(release_for("1.16.4"), Some("1.16.4"));
+        // 26.1 is not out yet
+        assert_eq!(release_for("26.1-snapshot-1"), None);
+        assert!(root.release_for("1.18").is_err());
+    }
+
+    #[test]
+    fn compares_snapshots_with_releases() {
+        let root = around_1_17();
+        let cases = [
+            ("1.16.4", "20w45a", Ordering::Less),
+            ("20w45a", "1.16.5", Ordering::Greater),
+            ("20w45a", "1.16.5-rc1", Ordering::Greater),
+            ("20w45a", "20w46a", Ordering::Less),
+            ("21w03a", "1.17-pre1", Ordering::Less),
+            ("20w45a", "1.17", Ordering::Less),
+            ("1.17", "26.1-snapshot-1", Ordering::Less),
+            ("26.1-snapshot-1", "26.1-pre-1", Ordering::Less),
+            ("1.17", "1.17", Ordering::Equal),
+        ];
+        for (a, b, ordering) in cases {
+            assert_eq!(root.compare(a, b).unwrap(), ordering, "{a} vs {b}");
+            assert_eq!(
+                root.compare(b, a).unwrap(),
+                ordering.reverse(),
+                "{b} vs {a}"
+            );
+        }
+    }
+}